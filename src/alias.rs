@@ -0,0 +1,118 @@
+//! # Alias maps
+//! `apply_alias_map()` consolidates keys using an externally curated table of synonyms. Each
+//! key's count moves to its canonical key, found by following the map from alias to alias until
+//! reaching a key that is not itself an alias. A key mapped to itself is canonical.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//! use std::collections::HashMap;
+//!
+//! let mut cities: HashHistogram<&str> = ["NYC", "New York", "nyc", "Boston"].iter().collect();
+//! let aliases = HashMap::from([("nyc", "NYC"), ("NYC", "New York")]);
+//! cities.apply_alias_map(&aliases).unwrap();
+//! assert_eq!(cities.count(&"New York"), 3);
+//! assert_eq!(cities.count(&"NYC"), 0);
+//! assert_eq!(cities.len(), 2);
+//! ```
+//!
+//! If the aliases of a key in the histogram lead around a cycle, the map is rejected and the
+//! histogram is left unchanged.
+//!
+//! ```
+//! use hash_histogram::{AliasCycle, HashHistogram};
+//! use std::collections::HashMap;
+//!
+//! let mut h: HashHistogram<&str> = ["a", "c"].iter().collect();
+//! let aliases = HashMap::from([("a", "b"), ("b", "a")]);
+//! assert_eq!(h.apply_alias_map(&aliases), Err(AliasCycle { key: "a" }));
+//! assert_eq!(h.count(&"a"), 1);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+
+/// An alias map rejected because following the aliases of `key` never reaches a canonical key.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AliasCycle<T> {
+    pub key: T,
+}
+
+impl<T: Debug> fmt::Display for AliasCycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "aliases of {:?} form a cycle", self.key)
+    }
+}
+
+impl<T: Debug> std::error::Error for AliasCycle<T> {}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Moves the count of every aliased key to its canonical key. Aliases are resolved once per
+    /// key, so the work is linear in the sizes of the histogram and the map.
+    pub fn apply_alias_map(&mut self, aliases: &HashMap<T, T>) -> Result<(), AliasCycle<T>> {
+        let mut canonical: HashMap<T, T> = HashMap::new();
+        for key in self.histogram.keys() {
+            resolve(key, aliases, &mut canonical)?;
+        }
+        let mut result = HashHistogram::new();
+        for (key, count) in self.histogram.drain() {
+            result.bump_by(canonical.get(&key).unwrap_or(&key), count);
+        }
+        *self = result;
+        Ok(())
+    }
+}
+
+/// Records the canonical key of `key` and of every alias on the way to it.
+fn resolve<T: KeyType>(
+    key: &T,
+    aliases: &HashMap<T, T>,
+    canonical: &mut HashMap<T, T>,
+) -> Result<(), AliasCycle<T>> {
+    let mut path = vec![];
+    let mut visited = HashSet::new();
+    let mut current = key;
+    let target = loop {
+        if let Some(known) = canonical.get(current) {
+            break known.clone();
+        }
+        match aliases.get(current) {
+            Some(next) if next != current => {
+                if !visited.insert(current) {
+                    return Err(AliasCycle { key: key.clone() });
+                }
+                path.push(current.clone());
+                current = next;
+            }
+            _ => break current.clone(),
+        }
+    };
+    for alias in path {
+        canonical.insert(alias, target.clone());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_chains() {
+        let mut h = HashHistogram::<u32, u64>::new();
+        let mut aliases = HashMap::new();
+        for i in 0..1000 {
+            h.bump_by(&i, 2);
+            aliases.insert(i, i + 1);
+        }
+        aliases.insert(1000, 1000);
+        h.apply_alias_map(&aliases).unwrap();
+        assert_eq!(h.len(), 1);
+        assert_eq!(h.count(&1000), 2000);
+
+        aliases.insert(1000, 500);
+        let err = h.apply_alias_map(&aliases).unwrap_err();
+        assert_eq!(err.to_string(), "aliases of 1000 form a cycle");
+        assert_eq!(h.count(&1000), 2000);
+    }
+}
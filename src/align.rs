@@ -0,0 +1,109 @@
+//! # Aligning histograms
+//! `align()` lays out the counts of several histograms over the union of their keys, as a dense
+//! matrix with one row per key and one column per histogram, filling in zeros for missing keys.
+//! Rows are sorted by key. This is the usual starting point for comparing many per-entity
+//! frequency profiles, as with clustering or principal component analysis.
+//!
+//! ```
+//! use hash_histogram::{align, HashHistogram};
+//!
+//! let alice: HashHistogram<&str> = ["login", "view", "view"].iter().collect();
+//! let bob: HashHistogram<&str> = ["view", "buy"].iter().collect();
+//! let aligned = align(&[&alice, &bob]);
+//! assert_eq!(aligned.keys, vec!["buy", "login", "view"]);
+//! assert_eq!(aligned.counts, vec![vec![0, 1], vec![1, 0], vec![2, 1]]);
+//! assert_eq!(aligned.column(1), vec![1, 0, 1]);
+//! assert_eq!(aligned.row(&"view"), Some(&[2, 1][..]));
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::BTreeSet;
+
+/// Counts of several histograms over a shared, sorted set of keys. `counts[i][j]` is the count
+/// of `keys[i]` in histogram `j`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AlignedCounts<T, C = usize> {
+    pub keys: Vec<T>,
+    pub counts: Vec<Vec<C>>,
+}
+
+pub fn align<T: KeyType + Ord, C: CounterType>(
+    histograms: &[&HashHistogram<T, C>],
+) -> AlignedCounts<T, C> {
+    let keys: Vec<T> = histograms
+        .iter()
+        .flat_map(|h| h.keys())
+        .collect::<BTreeSet<&T>>()
+        .into_iter()
+        .cloned()
+        .collect();
+    let counts = keys
+        .iter()
+        .map(|key| histograms.iter().map(|h| h.count(key)).collect())
+        .collect();
+    AlignedCounts { keys, counts }
+}
+
+impl<T: Ord, C: CounterType> AlignedCounts<T, C> {
+    /// The counts of `key` in each histogram, or `None` if no histogram contains it.
+    pub fn row(&self, key: &T) -> Option<&[C]> {
+        let i = self.keys.binary_search(key).ok()?;
+        Some(&self.counts[i])
+    }
+
+    /// The counts of histogram `j` for each key. Panics if `j` is out of range.
+    pub fn column(&self, j: usize) -> Vec<C> {
+        self.counts.iter().map(|row| row[j]).collect()
+    }
+
+    /// The counts with each column divided by its total, so that each histogram's column sums
+    /// to 1 and histograms of different sizes can be compared. Empty histograms have columns of
+    /// zeros.
+    pub fn normalized(&self) -> Vec<Vec<f64>> {
+        let columns = self.counts.first().map_or(0, |row| row.len());
+        let totals: Vec<f64> = (0..columns)
+            .map(|j| self.counts.iter().map(|row| row[j].to_f64().unwrap()).sum())
+            .collect();
+        self.counts
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&totals)
+                    .map(|(count, total)| {
+                        if *total > 0.0 {
+                            count.to_f64().unwrap() / total
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_columns() {
+        let a: HashHistogram<u8> = [1, 1, 2, 3].iter().collect();
+        let b: HashHistogram<u8> = [3].iter().collect();
+        let empty = HashHistogram::with_labels([4]);
+        let aligned = align(&[&a, &b, &empty]);
+        assert_eq!(aligned.keys, vec![1, 2, 3, 4]);
+        assert_eq!(aligned.row(&4), Some(&[0, 0, 0][..]));
+        assert_eq!(aligned.row(&5), None);
+        assert_eq!(
+            aligned.normalized(),
+            vec![
+                vec![0.5, 0.0, 0.0],
+                vec![0.25, 0.0, 0.0],
+                vec![0.25, 1.0, 0.0],
+                vec![0.0, 0.0, 0.0],
+            ]
+        );
+        assert!(align::<u8, usize>(&[]).keys.is_empty());
+    }
+}
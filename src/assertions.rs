@@ -0,0 +1,132 @@
+//! # Assertions for tests
+//! With the `test-util` feature, histograms gain assertions for statistical tests of simulation
+//! output. Each panics with a message describing every key that failed, rather than only the
+//! first.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//! use std::collections::HashMap;
+//!
+//! let rolls: HashHistogram<u8> = (0..6000).map(|i| (i * 7 % 6) as u8 + 1).collect();
+//! rolls.assert_covers(1..=6);
+//! rolls.assert_min_count(&6, 900);
+//! let fair: HashMap<u8, f64> = (1..=6).map(|face| (face, 1.0 / 6.0)).collect();
+//! rolls.assert_all_within(&fair, 0.01);
+//! ```
+//!
+//! ```should_panic
+//! use hash_histogram::HashHistogram;
+//! use std::collections::HashMap;
+//!
+//! let flips: HashHistogram<&str> = ["heads", "heads", "heads", "tails"].iter().collect();
+//! let fair = HashMap::from([("heads", 0.5), ("tails", 0.5)]);
+//! // Panics with:
+//! // 2 keys outside tolerance 0.1:
+//! //   "heads": observed 0.750, expected 0.500
+//! //   "tails": observed 0.250, expected 0.500
+//! flips.assert_all_within(&fair, 0.1);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+
+impl<T: KeyType + Debug, C: CounterType + Display> HashHistogram<T, C> {
+    #[track_caller]
+    pub fn assert_min_count(&self, item: &T, min: C) {
+        let count = self.count(item);
+        assert!(
+            count >= min,
+            "count of {:?} is {}, expected at least {}",
+            item,
+            count,
+            min
+        );
+    }
+
+    #[track_caller]
+    pub fn assert_max_count(&self, item: &T, max: C) {
+        let count = self.count(item);
+        assert!(
+            count <= max,
+            "count of {:?} is {}, expected at most {}",
+            item,
+            count,
+            max
+        );
+    }
+
+    /// Asserts that each of `labels` has a positive count.
+    #[track_caller]
+    pub fn assert_covers<I: IntoIterator<Item = T>>(&self, labels: I) {
+        let missing: Vec<T> = labels
+            .into_iter()
+            .filter(|label| self.count(label) == num::zero())
+            .collect();
+        assert!(missing.is_empty(), "never observed: {:?}", missing);
+    }
+
+    /// Asserts that every key's share of the total count is within `tolerance` of its expected
+    /// probability. Keys missing from `expected` are expected to have probability zero.
+    #[track_caller]
+    pub fn assert_all_within(&self, expected: &HashMap<T, f64>, tolerance: f64) {
+        let total = self.total_count().to_f64().unwrap();
+        let observed = |key: &T| {
+            if total > 0.0 {
+                self.count(key).to_f64().unwrap() / total
+            } else {
+                0.0
+            }
+        };
+        let unexpected = self.iter().map(|(key, _)| key);
+        let mut failures: Vec<(String, f64, f64)> = expected
+            .keys()
+            .chain(unexpected.filter(|key| !expected.contains_key(*key)))
+            .map(|key| {
+                let p = expected.get(key).copied().unwrap_or(0.0);
+                (format!("{:?}", key), observed(key), p)
+            })
+            .filter(|(_, observed, p)| (observed - p).abs() > tolerance)
+            .collect();
+        if !failures.is_empty() {
+            failures.sort_by(|(k1, _, _), (k2, _, _)| k1.cmp(k2));
+            let mut message = format!("{} keys outside tolerance {}:", failures.len(), tolerance);
+            for (key, observed, p) in failures {
+                message.push_str(&format!(
+                    "\n  {}: observed {:.3}, expected {:.3}",
+                    key, observed, p
+                ));
+            }
+            panic!("{}", message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_messages() {
+        let h: HashHistogram<char> = "aab".chars().collect();
+        let message = |f: &dyn Fn()| {
+            let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_err();
+            payload.downcast_ref::<String>().unwrap().clone()
+        };
+        assert_eq!(
+            message(&|| h.assert_min_count(&'b', 2)),
+            "count of 'b' is 1, expected at least 2"
+        );
+        assert_eq!(
+            message(&|| h.assert_covers("abcd".chars())),
+            "never observed: ['c', 'd']"
+        );
+        let expected = HashMap::from([('a', 1.0)]);
+        assert_eq!(
+            message(&|| h.assert_all_within(&expected, 0.2)),
+            "2 keys outside tolerance 0.2:\n  'a': observed 0.667, expected 1.000\n  \
+             'b': observed 0.333, expected 0.000"
+        );
+        h.assert_max_count(&'a', 2);
+    }
+}
@@ -0,0 +1,137 @@
+//! # Bandit helpers
+//! A pair of histograms counting the successes and failures of each key (an "arm") is enough to
+//! drive a multi-armed bandit. `thompson_sample()` draws a plausible success rate for every arm
+//! from its Beta posterior and picks the arm with the highest draw, so arms are chosen in
+//! proportion to the probability that they are the best.
+//!
+//! Randomness comes from a caller-supplied closure returning uniform samples from `[0, 1)`,
+//! which lets any random number generator crate drive the sampling.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let mut successes = HashHistogram::<&str>::new();
+//! let mut failures = HashHistogram::<&str>::new();
+//! successes.bump_by(&"blue button", 90);
+//! failures.bump_by(&"blue button", 10);
+//! successes.bump_by(&"red button", 10);
+//! failures.bump_by(&"red button", 90);
+//!
+//! // A fixed sequence stands in for a random number generator here.
+//! let mut state = 0.0;
+//! let mut uniform = || {
+//!     state = (state + 0.618034) % 1.0;
+//!     state
+//! };
+//! assert_eq!(successes.thompson_sample(&failures, &mut uniform), Some("blue button"));
+//! ```
+//!
+//! When rewards are not simply successes and failures, `ucb_scores()` combines a histogram of
+//! pulls per arm with a `WeightedHistogram` summing the rewards of each arm, and computes UCB1
+//! scores. Choosing the arm with the highest score balances exploration and exploitation.
+//!
+//! ```
+//! use hash_histogram::{HashHistogram, WeightedHistogram};
+//!
+//! let mut pulls = HashHistogram::<&str>::new();
+//! let mut rewards = WeightedHistogram::new();
+//! for (arm, reward) in [("a", 1.0), ("a", 0.0), ("a", 1.0), ("b", 0.5)].iter() {
+//!     pulls.bump(arm);
+//!     rewards.bump_by(arm, *reward);
+//! }
+//! let scores = pulls.ucb_scores(&rewards, pulls.total_count());
+//! // "b" has been tried only once, so its score carries a larger exploration bonus.
+//! assert!(scores[&"b"] > scores[&"a"]);
+//! ```
+
+use crate::stats::sample_beta;
+use crate::{CounterType, HashHistogram, KeyType, WeightedHistogram};
+use std::collections::HashMap;
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Treats `self` as success counts and `failures` as failure counts, draws a success rate
+    /// for every key in either histogram from Beta(successes + 1, failures + 1), and returns the
+    /// key with the highest draw. `rng` must return uniform samples from `[0, 1)`. Returns `None`
+    /// if both histograms are empty.
+    pub fn thompson_sample<R: FnMut() -> f64>(&self, failures: &Self, rng: &mut R) -> Option<T> {
+        let failed_only = failures
+            .labels()
+            .filter(|key| !self.histogram.contains_key(*key));
+        self.labels()
+            .chain(failed_only)
+            .map(|key| {
+                let a = self.count(key).to_f64().unwrap() + 1.0;
+                let b = failures.count(key).to_f64().unwrap() + 1.0;
+                (key, sample_beta(a, b, rng))
+            })
+            .max_by(|(_, x), (_, y)| x.total_cmp(y))
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Treats `self` as the number of pulls of each key and `rewards` as the sum of the rewards
+    /// each key has earned, and computes the UCB1 score `mean_reward + sqrt(2 ln(total_pulls) /
+    /// pulls)` of every key in `self`. Keys with no pulls, such as those from `with_labels()`,
+    /// score `f64::INFINITY`, so that every arm is tried before any is tried twice.
+    pub fn ucb_scores(&self, rewards: &WeightedHistogram<T>, total_pulls: C) -> HashMap<T, f64> {
+        let log_total = total_pulls.to_f64().unwrap().ln();
+        self.iter()
+            .map(|(key, pulls)| {
+                let pulls = pulls.to_f64().unwrap();
+                let score = if pulls > 0.0 {
+                    rewards.weight(key) / pulls + (2.0 * log_total / pulls).sqrt()
+                } else {
+                    f64::INFINITY
+                };
+                (key.clone(), score)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::test_rng;
+
+    #[test]
+    fn test_thompson_prefers_better_arm() {
+        let mut successes = HashHistogram::<u8>::new();
+        let mut failures = HashHistogram::<u8>::new();
+        successes.bump_by(&0, 30);
+        failures.bump_by(&0, 70);
+        successes.bump_by(&1, 45);
+        failures.bump_by(&1, 55);
+        failures.bump_by(&2, 3);
+
+        let mut rng = test_rng(7);
+        let picks: HashHistogram<u8> = (0..1000)
+            .map(|_| successes.thompson_sample(&failures, &mut rng).unwrap())
+            .collect();
+        assert_eq!(picks.mode(), Some(1));
+        assert!(picks.count(&1) > 800);
+
+        let empty = HashHistogram::<u8>::new();
+        assert_eq!(empty.thompson_sample(&empty, &mut rng), None);
+    }
+
+    #[test]
+    fn test_ucb_scores() {
+        let mut pulls = HashHistogram::<char, u32>::new();
+        let mut rewards = WeightedHistogram::new();
+        pulls.bump_by(&'x', 10);
+        rewards.bump_by(&'x', 7.0);
+        pulls.bump_by(&'y', 10);
+        rewards.bump_by(&'y', 2.0);
+        let scores = pulls.ucb_scores(&rewards, 100);
+        let bonus = (2.0 * 100f64.ln() / 10.0).sqrt();
+        assert!((scores[&'x'] - (0.7 + bonus)).abs() < 1e-12);
+        assert!((scores[&'y'] - (0.2 + bonus)).abs() < 1e-12);
+        assert_eq!(scores.len(), 2);
+
+        let mut untried = HashHistogram::<char, u32>::with_labels(['z']);
+        untried.bump_by(&'x', 10);
+        let scores = untried.ucb_scores(&rewards, 10);
+        assert_eq!(scores[&'z'], f64::INFINITY);
+        assert!(scores[&'x'].is_finite());
+    }
+}
@@ -0,0 +1,102 @@
+//! # Bayesian estimates
+//! `dirichlet_posterior()` treats the counts of a histogram as multinomial observations under a
+//! symmetric Dirichlet prior. Each key then has a Beta-distributed posterior probability, which
+//! is summarized by its mean and an equal-tailed credible interval. With few observations, the
+//! prior pulls estimates away from the raw frequencies and the intervals stay wide.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<&str> = ["heads", "heads", "tails"].iter().collect();
+//! let posterior = h.dirichlet_posterior(1.0);
+//! let heads = posterior[&"heads"];
+//! assert_eq!(heads.mean, 0.6);
+//! assert!(heads.lower < 0.25 && heads.upper > 0.9);
+//! ```
+
+use crate::stats::beta_quantile;
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::HashMap;
+
+/// Posterior summary for the probability of a single key.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PosteriorSummary {
+    pub mean: f64,
+    /// Lower end of the credible interval.
+    pub lower: f64,
+    /// Upper end of the credible interval.
+    pub upper: f64,
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Summarizes the posterior probability of each key with a 95% credible interval, using a
+    /// symmetric Dirichlet prior with concentration `alpha` over the keys in the histogram.
+    pub fn dirichlet_posterior(&self, alpha: f64) -> HashMap<T, PosteriorSummary> {
+        self.dirichlet_posterior_with(alpha, 0.95)
+    }
+
+    /// As `dirichlet_posterior()`, with credible intervals covering `level` of the posterior
+    /// probability. A histogram with a single key puts all of its posterior probability on that
+    /// key, so the interval is `[1, 1]`. Panics unless `alpha` is positive and `level` lies
+    /// strictly between 0 and 1.
+    pub fn dirichlet_posterior_with(&self, alpha: f64, level: f64) -> HashMap<T, PosteriorSummary> {
+        assert!(alpha > 0.0, "alpha must be positive");
+        assert!(level > 0.0 && level < 1.0, "level must be between 0 and 1");
+        let total = self.total_count().to_f64().unwrap() + alpha * self.len() as f64;
+        let tail = (1.0 - level) / 2.0;
+        self.iter()
+            .map(|(key, count)| {
+                let a = count.to_f64().unwrap() + alpha;
+                let b = total - a;
+                let summary = if b > 0.0 {
+                    PosteriorSummary {
+                        mean: a / total,
+                        lower: beta_quantile(tail, a, b),
+                        upper: beta_quantile(1.0 - tail, a, b),
+                    }
+                } else {
+                    PosteriorSummary {
+                        mean: 1.0,
+                        lower: 1.0,
+                        upper: 1.0,
+                    }
+                };
+                (key.clone(), summary)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posterior() {
+        let mut h = HashHistogram::<char, u64>::new();
+        h.bump_by(&'a', 98);
+        h.bump_by(&'b', 2);
+        let posterior = h.dirichlet_posterior_with(0.5, 0.9);
+        let a = posterior[&'a'];
+        let b = posterior[&'b'];
+        assert!((a.mean - 98.5 / 101.0).abs() < 1e-12);
+        assert!((a.mean + b.mean - 1.0).abs() < 1e-12);
+        assert!(a.lower < a.mean && a.mean < a.upper);
+        assert!((a.lower + b.upper - 1.0).abs() < 1e-9);
+        assert!(b.upper < 0.06);
+    }
+
+    #[test]
+    fn test_single_key() {
+        let h: HashHistogram<&str> = ["a", "a"].iter().collect();
+        let a = h.dirichlet_posterior(1.0)[&"a"];
+        assert_eq!(
+            a,
+            PosteriorSummary {
+                mean: 1.0,
+                lower: 1.0,
+                upper: 1.0
+            }
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! # Binary checkpoints
+//! `to_bytes()` encodes a histogram in a compact binary format for checkpointing to disk, and
+//! `from_bytes()` reads it back. The format is stable: it begins with a magic number and a
+//! version byte, and future versions of this crate will continue to read version 1.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<String> = ["to", "be", "or", "not", "to", "be"]
+//!     .iter()
+//!     .map(|s| s.to_string())
+//!     .collect();
+//! let bytes = h.to_bytes();
+//! assert_eq!(HashHistogram::from_bytes(&bytes).unwrap(), h);
+//! ```
+//!
+//! Version 1 consists of the bytes `HHST`, the version byte `1`, the number of entries, and then
+//! each entry's key followed by its count, in increasing key order. Counts and lengths are
+//! unsigned LEB128 varints. Keys are encoded by the `BinaryKey` trait:
+//! * Unsigned integers are varints, and signed integers are zigzag-encoded varints.
+//! * A `char` is the varint of its code point, and a `bool` is one byte, `0` or `1`.
+//! * A `String` is the varint of its length in bytes followed by its UTF-8 bytes.
+//! * A pair or triple is the concatenation of its components.
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"HHST";
+const VERSION: u8 = 1;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BinaryError {
+    /// The input does not begin with the magic number.
+    NotAHistogram,
+    UnsupportedVersion(u8),
+    /// The input ended partway through an entry.
+    Truncated,
+    InvalidKey,
+    /// A key was repeated or out of order.
+    UnsortedKeys,
+    /// A count, or a varint key, is too large for its type.
+    Overflow,
+    /// Bytes remained after the last entry.
+    TrailingBytes,
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::NotAHistogram => write!(f, "not an encoded histogram"),
+            BinaryError::UnsupportedVersion(v) => write!(f, "unsupported format version {}", v),
+            BinaryError::Truncated => write!(f, "unexpected end of input"),
+            BinaryError::InvalidKey => write!(f, "invalid key"),
+            BinaryError::UnsortedKeys => write!(f, "keys are not in strictly increasing order"),
+            BinaryError::Overflow => write!(f, "value too large for its type"),
+            BinaryError::TrailingBytes => write!(f, "unexpected bytes after the last entry"),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+/// Keys that can be written by `to_bytes()` and read by `from_bytes()`.
+pub trait BinaryKey: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Reads a key from the front of `input`, advancing it past the bytes read.
+    fn decode(input: &mut &[u8]) -> Result<Self, BinaryError>;
+}
+
+impl<T: KeyType + Ord + BinaryKey, C: CounterType> HashHistogram<T, C> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<(&T, &C)> = self.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        let mut out = MAGIC.to_vec();
+        out.push(VERSION);
+        write_varint(entries.len() as u128, &mut out);
+        for (key, count) in entries {
+            key.encode(&mut out);
+            write_varint(count.to_u128().unwrap(), &mut out);
+        }
+        out
+    }
+
+    /// Rejects input whose keys are not in strictly increasing order, as `to_bytes()` writes
+    /// them, since it cannot have been written by `to_bytes()`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BinaryError> {
+        let mut input = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or(BinaryError::NotAHistogram)?;
+        match take_byte(&mut input)? {
+            VERSION => {}
+            v => return Err(BinaryError::UnsupportedVersion(v)),
+        }
+        let entries = read_varint(&mut input)?;
+        let mut result = HashHistogram::new();
+        let mut previous: Option<T> = None;
+        for _ in 0..entries {
+            let key = T::decode(&mut input)?;
+            if previous.as_ref().is_some_and(|previous| *previous >= key) {
+                return Err(BinaryError::UnsortedKeys);
+            }
+            let count =
+                <C as num::NumCast>::from(read_varint(&mut input)?).ok_or(BinaryError::Overflow)?;
+            result.bump_by(&key, count);
+            previous = Some(key);
+        }
+        if input.is_empty() {
+            Ok(result)
+        } else {
+            Err(BinaryError::TrailingBytes)
+        }
+    }
+}
+
+pub(crate) fn write_varint(mut value: u128, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+pub(crate) fn read_varint(input: &mut &[u8]) -> Result<u128, BinaryError> {
+    let mut value = 0u128;
+    for shift in (0..128).step_by(7) {
+        let byte = take_byte(input)?;
+        let bits = (byte & 0x7f) as u128;
+        if bits << shift >> shift != bits {
+            return Err(BinaryError::Overflow);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(BinaryError::Overflow)
+}
+
+fn take_byte(input: &mut &[u8]) -> Result<u8, BinaryError> {
+    let (&byte, rest) = input.split_first().ok_or(BinaryError::Truncated)?;
+    *input = rest;
+    Ok(byte)
+}
+
+macro_rules! unsigned_binary_key {
+    ($($t:ty),*) => {$(
+        impl BinaryKey for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                write_varint(*self as u128, out);
+            }
+
+            fn decode(input: &mut &[u8]) -> Result<Self, BinaryError> {
+                <$t>::try_from(read_varint(input)?).map_err(|_| BinaryError::Overflow)
+            }
+        }
+    )*};
+}
+
+macro_rules! signed_binary_key {
+    ($($t:ty),*) => {$(
+        impl BinaryKey for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                let value = *self as i128;
+                write_varint(((value << 1) ^ (value >> 127)) as u128, out);
+            }
+
+            fn decode(input: &mut &[u8]) -> Result<Self, BinaryError> {
+                let zigzag = read_varint(input)?;
+                let value = (zigzag >> 1) as i128 ^ -((zigzag & 1) as i128);
+                <$t>::try_from(value).map_err(|_| BinaryError::Overflow)
+            }
+        }
+    )*};
+}
+
+unsigned_binary_key!(u8, u16, u32, u64, u128, usize);
+signed_binary_key!(i8, i16, i32, i64, i128, isize);
+
+impl BinaryKey for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(u8::from(*self));
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, BinaryError> {
+        match take_byte(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(BinaryError::InvalidKey),
+        }
+    }
+}
+
+impl BinaryKey for char {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u32).encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, BinaryError> {
+        char::from_u32(u32::decode(input)?).ok_or(BinaryError::InvalidKey)
+    }
+}
+
+impl BinaryKey for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, BinaryError> {
+        let len = usize::decode(input)?;
+        if input.len() < len {
+            return Err(BinaryError::Truncated);
+        }
+        let (bytes, rest) = input.split_at(len);
+        *input = rest;
+        String::from_utf8(bytes.to_vec()).map_err(|_| BinaryError::InvalidKey)
+    }
+}
+
+impl<A: BinaryKey, B: BinaryKey> BinaryKey for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, BinaryError> {
+        Ok((A::decode(input)?, B::decode(input)?))
+    }
+}
+
+impl<A: BinaryKey, B: BinaryKey, D: BinaryKey> BinaryKey for (A, B, D) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+        self.2.encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, BinaryError> {
+        Ok((A::decode(input)?, B::decode(input)?, D::decode(input)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_encoding() {
+        let mut h = HashHistogram::<(i32, char), u64>::new();
+        h.bump_by(&(-1, 'a'), 300);
+        h.bump(&(2, 'b'));
+        let bytes = h.to_bytes();
+        assert_eq!(
+            bytes,
+            vec![b'H', b'H', b'S', b'T', 1, 2, 1, 97, 0xac, 0x02, 4, 98, 1]
+        );
+        assert_eq!(HashHistogram::from_bytes(&bytes).unwrap(), h);
+    }
+
+    #[test]
+    fn test_decoding_errors() {
+        let h: HashHistogram<u16, u16> = [(7, 1000)].into_iter().collect();
+        let bytes = h.to_bytes();
+        let decode = |bytes: &[u8]| HashHistogram::<u16, u8>::from_bytes(bytes);
+        assert_eq!(decode(&bytes), Err(BinaryError::Overflow));
+        assert_eq!(decode(&bytes[..7]), Err(BinaryError::Truncated));
+        assert_eq!(decode(b"HHST\x02"), Err(BinaryError::UnsupportedVersion(2)));
+        assert_eq!(decode(b"{}"), Err(BinaryError::NotAHistogram));
+        assert_eq!(decode(b"HHST\x01\x00\x00"), Err(BinaryError::TrailingBytes));
+        assert_eq!(
+            decode(b"HHST\x01\x02\x07\x05\x07\x01"),
+            Err(BinaryError::UnsortedKeys)
+        );
+        assert_eq!(
+            decode(b"HHST\x01\x02\x07\x01\x03\x01"),
+            Err(BinaryError::UnsortedKeys)
+        );
+        assert_eq!(
+            HashHistogram::<u16, u16>::from_bytes(&bytes)
+                .unwrap()
+                .count(&7),
+            1000
+        );
+    }
+}
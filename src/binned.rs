@@ -0,0 +1,539 @@
+//! # Binned histograms
+//! `BinnedHistogram` groups continuous values into bins delimited by a sorted list of edges.
+//! Each observation may carry a weight and an associated value. Every bin tracks the weighted
+//! sum, mean, and variance of the values recorded in it, which covers "profile histogram" use
+//! cases as well as plain counting.
+//!
+//! ```
+//! use hash_histogram::BinnedHistogram;
+//!
+//! let mut h = BinnedHistogram::uniform(0.0, 10.0, 5);
+//! for x in [0.5, 1.5, 2.5, 3.0, 9.0, 12.0].iter() {
+//!     h.record(*x);
+//! }
+//! assert_eq!(h.bin(0).count(), 2);
+//! assert_eq!(h.bin(1).count(), 2);
+//! assert_eq!(h.bin(4).count(), 1);
+//! assert_eq!(h.overflow().count(), 1);
+//!
+//! // Weighted observations of an associated value
+//! let mut profile = BinnedHistogram::with_edges(vec![0.0, 1.0, 2.0]);
+//! profile.record_value(0.25, 10.0, 1.0);
+//! profile.record_value(0.75, 20.0, 3.0);
+//! assert_eq!(profile.bin(0).weight(), 4.0);
+//! assert_eq!(profile.bin(0).sum(), 70.0);
+//! assert_eq!(profile.bin(0).mean(), Some(17.5));
+//! assert_eq!(profile.bin(0).variance(), Some(18.75));
+//!
+//! // Edges chosen automatically from a sample, which is then recorded
+//! let sample = [1.0, 2.0, 2.5, 3.0, 3.5, 4.0, 4.5, 5.0, 8.0];
+//! let auto = BinnedHistogram::auto(&sample).unwrap();
+//! assert_eq!(auto.total_count(), sample.len());
+//!
+//! // Comparing two histograms bin by bin
+//! let mut control = BinnedHistogram::uniform(0.0, 100.0, 2);
+//! let mut treatment = BinnedHistogram::uniform(0.0, 100.0, 2);
+//! for ms in [10.0, 20.0, 30.0, 60.0].iter() {
+//!     control.record(*ms);
+//! }
+//! for ms in [15.0, 70.0, 80.0].iter() {
+//!     treatment.record(*ms);
+//! }
+//! let comparison = control.compare_bins(&treatment);
+//! assert_eq!(comparison[0].ratio, Some(3.0));
+//! assert_eq!(comparison[1].ratio, Some(0.5));
+//!
+//! // Logarithmic bins for durations, labeled by their ranges
+//! let mut latency = BinnedHistogram::log_scale(0.001, 10.0, 4);
+//! for ms in [2, 3, 40, 45, 50].iter() {
+//!     latency.record_duration(std::time::Duration::from_millis(*ms));
+//! }
+//! assert_eq!(latency.bin_label(1), "[0.01, 0.1)");
+//! assert_eq!(latency.ranking()[0], latency.bin_range(1));
+//! assert_eq!(latency.to_string(), "[0.001, 0.01):2; [0.01, 0.1):3; [0.1, 1):0; [1, 10]:0; ");
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Range;
+use std::time::Duration;
+
+/// Running statistics for the observations recorded in a single bin.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BinStats {
+    count: usize,
+    weight: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl BinStats {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Weighted sum of the recorded values.
+    pub fn sum(&self) -> f64 {
+        self.mean * self.weight
+    }
+
+    /// Weighted mean of the recorded values, or `None` if the bin has no weight.
+    pub fn mean(&self) -> Option<f64> {
+        (self.weight > 0.0).then_some(self.mean)
+    }
+
+    /// Weighted population variance of the recorded values, or `None` if the bin has no weight.
+    pub fn variance(&self) -> Option<f64> {
+        (self.weight > 0.0).then(|| self.m2 / self.weight)
+    }
+
+    fn record(&mut self, value: f64, weight: f64) {
+        self.count += 1;
+        self.weight += weight;
+        if self.weight > 0.0 {
+            let delta = value - self.mean;
+            self.mean += delta * weight / self.weight;
+            self.m2 += weight * delta * (value - self.mean);
+        }
+    }
+}
+
+/// Standard rules for choosing bins from a data sample.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum BinRule {
+    /// `ceil(log2(n)) + 1` bins, which suits roughly normal data.
+    Sturges,
+    /// Bins of width `2 * IQR / cbrt(n)`, which is robust to outliers.
+    FreedmanDiaconis,
+    /// Whichever of Sturges and Freedman-Diaconis yields more bins.
+    #[default]
+    Auto,
+}
+
+impl BinRule {
+    fn num_bins(&self, sorted: &[f64]) -> usize {
+        let n = sorted.len();
+        let sturges = (n as f64).log2().ceil() as usize + 1;
+        let iqr = quantile(sorted, 0.75) - quantile(sorted, 0.25);
+        let range = sorted[n - 1] - sorted[0];
+        let freedman_diaconis = if iqr > 0.0 {
+            let width = 2.0 * iqr / (n as f64).cbrt();
+            ((range / width).ceil() as usize).clamp(1, n)
+        } else {
+            sturges
+        };
+        match self {
+            BinRule::Sturges => sturges,
+            BinRule::FreedmanDiaconis => freedman_diaconis,
+            BinRule::Auto => sturges.max(freedman_diaconis),
+        }
+    }
+}
+
+fn valid_edges(edges: &[f64]) -> bool {
+    edges.iter().all(|e| e.is_finite()) && edges.windows(2).all(|w| w[0] < w[1])
+}
+
+fn uniform_edges(low: f64, high: f64, bins: usize) -> Vec<f64> {
+    let mut width = (high - low) / bins as f64;
+    if width.is_infinite() {
+        // The range itself overflows, but its fractions need not.
+        width = high / bins as f64 - low / bins as f64;
+    }
+    let mut edges: Vec<f64> = (0..bins).map(|i| low + width * i as f64).collect();
+    edges.push(high);
+    edges
+}
+
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+/// One bin of the alignment produced by `BinnedHistogram::compare_bins()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinComparison {
+    pub range: Range<f64>,
+    /// Weight of the histogram on which `compare_bins()` was called.
+    pub left: f64,
+    /// Weight of the histogram passed to `compare_bins()`.
+    pub right: f64,
+    /// `left / right`, or `None` if `right` is zero.
+    pub ratio: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BinnedHistogram {
+    edges: Vec<f64>,
+    bins: Vec<BinStats>,
+    underflow: BinStats,
+    overflow: BinStats,
+}
+
+impl BinnedHistogram {
+    /// Each bin covers `edges[i]..edges[i + 1]`, except that the last bin also includes its
+    /// upper edge. Panics unless there are at least two edges, all finite and strictly increasing.
+    pub fn with_edges(edges: Vec<f64>) -> Self {
+        assert!(edges.len() >= 2, "at least two bin edges are required");
+        assert!(
+            valid_edges(&edges),
+            "bin edges must be finite and strictly increasing"
+        );
+        BinnedHistogram {
+            bins: vec![BinStats::default(); edges.len() - 1],
+            edges,
+            underflow: BinStats::default(),
+            overflow: BinStats::default(),
+        }
+    }
+
+    /// Creates `bins` bins of equal width spanning `low..=high`.
+    pub fn uniform(low: f64, high: f64, bins: usize) -> Self {
+        assert!(bins > 0, "at least one bin is required");
+        Self::with_edges(uniform_edges(low, high, bins))
+    }
+
+    /// Creates `bins` bins spanning `low..=high` whose edges grow geometrically, so that each bin
+    /// covers the same ratio. Panics unless `low` is positive and less than `high`.
+    pub fn log_scale(low: f64, high: f64, bins: usize) -> Self {
+        assert!(bins > 0, "at least one bin is required");
+        assert!(
+            low > 0.0 && low < high,
+            "log scale bins require 0 < low < high"
+        );
+        let ratio = high / low;
+        let mut edges: Vec<f64> = (0..bins)
+            .map(|i| low * ratio.powf(i as f64 / bins as f64))
+            .collect();
+        edges.push(high);
+        Self::with_edges(edges)
+    }
+
+    /// Chooses uniform bins spanning `data` using `BinRule::Auto`, then records every value.
+    /// Returns `None` if `data` is empty or contains a non-finite value.
+    pub fn auto(data: &[f64]) -> Option<Self> {
+        Self::auto_with(data, BinRule::default())
+    }
+
+    /// Chooses uniform bins spanning `data` using `rule`, then records every value. The
+    /// Freedman-Diaconis rule never produces more bins than there are values. If the values are
+    /// so close together that the chosen bins cannot be represented, fewer bins are used.
+    /// Returns `None` if `data` is empty or contains a non-finite value, or if every value is
+    /// equal to one too large to surround with a bin.
+    pub fn auto_with(data: &[f64], rule: BinRule) -> Option<Self> {
+        if data.is_empty() || !data.iter().all(|x| x.is_finite()) {
+            return None;
+        }
+        let mut sorted = data.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let (low, high) = (sorted[0], sorted[sorted.len() - 1]);
+        let edges = if low == high {
+            let padding = 0.5f64.max(low.abs() * f64::EPSILON);
+            let edges = vec![low - padding, high + padding];
+            if !valid_edges(&edges) {
+                return None;
+            }
+            edges
+        } else {
+            let mut bins = rule.num_bins(&sorted);
+            loop {
+                let edges = uniform_edges(low, high, bins);
+                if bins == 1 || valid_edges(&edges) {
+                    break edges;
+                }
+                bins /= 2;
+            }
+        };
+        let mut result = Self::with_edges(edges);
+        for x in data {
+            result.record(*x);
+        }
+        Some(result)
+    }
+
+    pub fn edges(&self) -> &[f64] {
+        &self.edges
+    }
+
+    pub fn num_bins(&self) -> usize {
+        self.bins.len()
+    }
+
+    pub fn bin_range(&self, bin: usize) -> Range<f64> {
+        self.edges[bin]..self.edges[bin + 1]
+    }
+
+    /// Returns the bin containing `x`, or `None` if `x` lies outside the edges or is NaN.
+    pub fn bin_index(&self, x: f64) -> Option<usize> {
+        let last = self.edges.len() - 1;
+        if x == self.edges[last] {
+            Some(last - 1)
+        } else {
+            match self.edges.partition_point(|e| *e <= x) {
+                0 => None,
+                i if i > last => None,
+                i => Some(i - 1),
+            }
+        }
+    }
+
+    pub fn record(&mut self, x: f64) {
+        self.record_weighted(x, 1.0);
+    }
+
+    /// Records a duration, measured in seconds.
+    pub fn record_duration(&mut self, duration: Duration) {
+        self.record(duration.as_secs_f64());
+    }
+
+    /// Records `x` with the given weight, using `x` itself as the associated value.
+    pub fn record_weighted(&mut self, x: f64, weight: f64) {
+        self.record_value(x, x, weight);
+    }
+
+    /// Records an observation located at `x`, carrying `value` and `weight` into the statistics
+    /// of the bin containing `x`. Observations below the first edge go to `underflow()`; all
+    /// other out-of-range observations, including NaN, go to `overflow()`.
+    pub fn record_value(&mut self, x: f64, value: f64, weight: f64) {
+        let stats = match self.bin_index(x) {
+            Some(bin) => &mut self.bins[bin],
+            None if x < self.edges[0] => &mut self.underflow,
+            None => &mut self.overflow,
+        };
+        stats.record(value, weight);
+    }
+
+    /// The range of a bin in interval notation, such as `[0, 2)`. The last bin is closed.
+    pub fn bin_label(&self, bin: usize) -> String {
+        let close = if bin + 1 == self.num_bins() { ']' } else { ')' };
+        format!("[{}, {}{}", self.edges[bin], self.edges[bin + 1], close)
+    }
+
+    pub fn bin(&self, bin: usize) -> &BinStats {
+        &self.bins[bin]
+    }
+
+    pub fn bins(&self) -> impl Iterator<Item = (Range<f64>, &BinStats)> + '_ {
+        self.bins
+            .iter()
+            .enumerate()
+            .map(|(i, stats)| (self.bin_range(i), stats))
+    }
+
+    pub fn underflow(&self) -> &BinStats {
+        &self.underflow
+    }
+
+    pub fn overflow(&self) -> &BinStats {
+        &self.overflow
+    }
+
+    /// Number of observations recorded within the edges.
+    pub fn total_count(&self) -> usize {
+        self.bins.iter().map(|b| b.count).sum()
+    }
+
+    /// Total weight of the observations recorded within the edges.
+    pub fn total_weight(&self) -> f64 {
+        self.bins.iter().map(|b| b.weight).sum()
+    }
+
+    /// Bin ranges from highest to lowest count. Bins with equal counts keep their order.
+    pub fn ranking(&self) -> Vec<Range<f64>> {
+        self.ranking_with_counts()
+            .into_iter()
+            .map(|(range, _)| range)
+            .collect()
+    }
+
+    pub fn ranking_with_counts(&self) -> Vec<(Range<f64>, usize)> {
+        let mut ranking: Vec<(Range<f64>, usize)> = self
+            .bins()
+            .map(|(range, stats)| (range, stats.count))
+            .collect();
+        ranking.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+        ranking
+    }
+
+    /// Aligns the bins of both histograms and reports their weights side by side. If the edges
+    /// differ, both histograms are re-binned onto the union of their edges, splitting the weight
+    /// of each original bin in proportion to its overlap with each shared bin.
+    pub fn compare_bins(&self, other: &BinnedHistogram) -> Vec<BinComparison> {
+        let edges = if self.edges == other.edges {
+            self.edges.clone()
+        } else {
+            let mut union: Vec<f64> = self
+                .edges
+                .iter()
+                .chain(other.edges.iter())
+                .copied()
+                .collect();
+            union.sort_by(f64::total_cmp);
+            union.dedup();
+            union
+        };
+        let left = self.rebinned_weights(&edges);
+        let right = other.rebinned_weights(&edges);
+        edges
+            .windows(2)
+            .zip(left.into_iter().zip(right))
+            .map(|(range, (left, right))| BinComparison {
+                range: range[0]..range[1],
+                left,
+                right,
+                ratio: (right != 0.0).then(|| left / right),
+            })
+            .collect()
+    }
+
+    fn rebinned_weights(&self, edges: &[f64]) -> Vec<f64> {
+        edges
+            .windows(2)
+            .map(|target| {
+                self.bins()
+                    .map(|(source, stats)| {
+                        let overlap = target[1].min(source.end) - target[0].max(source.start);
+                        if overlap > 0.0 {
+                            stats.weight * overlap / (source.end - source.start)
+                        } else {
+                            0.0
+                        }
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+/// Lists each bin label with its count, in order of the edges.
+impl fmt::Display for BinnedHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (bin, stats) in self.bins.iter().enumerate() {
+            write!(f, "{}:{}; ", self.bin_label(bin), stats.count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bin_index() {
+        let h = BinnedHistogram::with_edges(vec![0.0, 1.0, 5.0, 10.0]);
+        assert_eq!(h.bin_index(-0.1), None);
+        assert_eq!(h.bin_index(0.0), Some(0));
+        assert_eq!(h.bin_index(1.0), Some(1));
+        assert_eq!(h.bin_index(9.99), Some(2));
+        assert_eq!(h.bin_index(10.0), Some(2));
+        assert_eq!(h.bin_index(10.01), None);
+        assert_eq!(h.bin_index(f64::NAN), None);
+    }
+
+    #[test]
+    fn test_weighted_stats() {
+        let mut h = BinnedHistogram::uniform(0.0, 4.0, 2);
+        h.record_weighted(0.5, 2.0);
+        h.record_weighted(1.5, 2.0);
+        h.record_value(3.0, 7.0, 0.5);
+        h.record(-1.0);
+        h.record(f64::NAN);
+
+        assert_eq!(h.bin(0).count(), 2);
+        assert_eq!(h.bin(0).weight(), 4.0);
+        assert_eq!(h.bin(0).mean(), Some(1.0));
+        assert_eq!(h.bin(0).variance(), Some(0.25));
+        assert_eq!(h.bin(1).sum(), 3.5);
+        assert_eq!(h.bin(1).variance(), Some(0.0));
+        assert_eq!(h.underflow().count(), 1);
+        assert_eq!(h.overflow().count(), 1);
+        assert_eq!(h.total_count(), 3);
+        assert_eq!(h.total_weight(), 4.5);
+        assert_eq!(BinStats::default().mean(), None);
+    }
+
+    #[test]
+    fn test_auto() {
+        let data: Vec<f64> = (0..100)
+            .map(|i| (i % 10) as f64 + i as f64 / 100.0)
+            .collect();
+        let sturges = BinnedHistogram::auto_with(&data, BinRule::Sturges).unwrap();
+        assert_eq!(sturges.num_bins(), 8);
+        assert_eq!(sturges.total_count(), 100);
+        assert_eq!(sturges.edges()[0], 0.0);
+        assert_eq!(*sturges.edges().last().unwrap(), 9.99);
+
+        let fd = BinnedHistogram::auto_with(&data, BinRule::FreedmanDiaconis).unwrap();
+        assert_eq!(fd.num_bins(), 5);
+        assert_eq!(BinnedHistogram::auto(&data).unwrap().num_bins(), 8);
+
+        let constant = BinnedHistogram::auto(&[3.0, 3.0]).unwrap();
+        assert_eq!(constant.num_bins(), 1);
+        assert_eq!(constant.bin(0).count(), 2);
+
+        assert!(BinnedHistogram::auto(&[]).is_none());
+        assert!(BinnedHistogram::auto(&[1.0, f64::INFINITY]).is_none());
+    }
+
+    #[test]
+    fn test_auto_extreme_ranges() {
+        let next = f64::from_bits(1.0f64.to_bits() + 1);
+        let close: Vec<f64> = (0..100)
+            .map(|i| if i % 2 == 0 { 1.0 } else { next })
+            .collect();
+        let h = BinnedHistogram::auto(&close).unwrap();
+        assert_eq!(h.edges(), &[1.0, next]);
+        assert_eq!(h.total_count(), 100);
+
+        let wide = BinnedHistogram::auto(&[-1e308, 1e308]).unwrap();
+        assert_eq!(wide.num_bins(), 2);
+        assert_eq!(wide.total_count(), 2);
+
+        let huge = BinnedHistogram::auto(&[1e308]).unwrap();
+        assert_eq!(huge.bin(0).count(), 1);
+        assert!(BinnedHistogram::auto(&[f64::MAX]).is_none());
+    }
+
+    #[test]
+    fn test_compare_rebinned() {
+        let mut coarse = BinnedHistogram::with_edges(vec![0.0, 4.0]);
+        coarse.record_weighted(1.0, 8.0);
+        let mut fine = BinnedHistogram::with_edges(vec![2.0, 3.0, 6.0]);
+        fine.record(2.5);
+        fine.record_weighted(5.0, 3.0);
+
+        let comparison = coarse.compare_bins(&fine);
+        let ranges: Vec<Range<f64>> = comparison.iter().map(|c| c.range.clone()).collect();
+        assert_eq!(ranges, vec![0.0..2.0, 2.0..3.0, 3.0..4.0, 4.0..6.0]);
+        let left: Vec<f64> = comparison.iter().map(|c| c.left).collect();
+        assert_eq!(left, vec![4.0, 2.0, 2.0, 0.0]);
+        let right: Vec<f64> = comparison.iter().map(|c| c.right).collect();
+        assert_eq!(right, vec![0.0, 1.0, 1.0, 2.0]);
+        let ratios: Vec<Option<f64>> = comparison.iter().map(|c| c.ratio).collect();
+        assert_eq!(ratios, vec![None, Some(2.0), Some(2.0), Some(0.0)]);
+    }
+
+    #[test]
+    fn test_log_scale() {
+        let mut h = BinnedHistogram::log_scale(1.0, 1000.0, 3);
+        assert_eq!(h.num_bins(), 3);
+        assert!((h.edges()[1] - 10.0).abs() < 1e-9);
+        assert!((h.edges()[2] - 100.0).abs() < 1e-9);
+        for x in [1.0, 5.0, 50.0, 500.0, 1000.0, 999.0].iter() {
+            h.record(*x);
+        }
+        let counts: Vec<usize> = h.ranking_with_counts().iter().map(|(_, c)| *c).collect();
+        assert_eq!(counts, vec![3, 2, 1]);
+        assert_eq!(h.ranking()[0].end, 1000.0);
+    }
+}
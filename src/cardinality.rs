@@ -0,0 +1,131 @@
+//! # Approximate distinct counts
+//! `ApproxLabelCounter` estimates how many distinct labels it has seen using HyperLogLog, in a
+//! fixed amount of memory. Counters built with the same precision can be merged, so per-shard
+//! counters combine into an estimate of the distinct labels across all shards, which the `len()`
+//! values of the per-shard histograms cannot provide.
+//!
+//! ```
+//! use hash_histogram::{ApproxLabelCounter, HashHistogram};
+//!
+//! let shard1: HashHistogram<u32> = (0..6000).collect();
+//! let shard2: HashHistogram<u32> = (4000..10000).collect();
+//!
+//! let mut labels = ApproxLabelCounter::from_histogram(&shard1, 12);
+//! labels.merge(&ApproxLabelCounter::from_histogram(&shard2, 12));
+//! let estimate = labels.cardinality_estimate() as f64;
+//! assert!((estimate - 10000.0).abs() < 10000.0 * 3.0 * labels.relative_error());
+//! ```
+//!
+//! Labels are hashed with `std::collections::hash_map::DefaultHasher`, whose algorithm may
+//! change between Rust releases. Only merge counters built with the same toolchain.
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ApproxLabelCounter<T: KeyType> {
+    precision: u8,
+    registers: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    labels: PhantomData<fn(&T)>,
+}
+
+impl<T: KeyType> ApproxLabelCounter<T> {
+    /// Creates a counter with `2^precision` one-byte registers. Panics unless `precision` is
+    /// between 4 and 16.
+    pub fn new(precision: u8) -> Self {
+        assert!(
+            (4..=16).contains(&precision),
+            "precision must be between 4 and 16"
+        );
+        ApproxLabelCounter {
+            precision,
+            registers: vec![0; 1 << precision],
+            labels: PhantomData,
+        }
+    }
+
+    pub fn from_histogram<C: CounterType>(histogram: &HashHistogram<T, C>, precision: u8) -> Self {
+        let mut result = Self::new(precision);
+        for (label, _) in histogram.iter() {
+            result.insert(label);
+        }
+        result
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn insert(&mut self, label: &T) {
+        let mut hasher = DefaultHasher::new();
+        label.hash(&mut hasher);
+        let hash = hasher.finish();
+        let register = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision) | (1 << (self.precision - 1))).leading_zeros() + 1;
+        self.registers[register] = self.registers[register].max(rank as u8);
+    }
+
+    /// Folds the labels seen by `other` into this counter. Panics if the precisions differ.
+    pub fn merge(&mut self, other: &Self) {
+        assert_eq!(self.precision, other.precision, "precisions must match");
+        for (mine, theirs) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *mine = (*mine).max(*theirs);
+        }
+    }
+
+    pub fn cardinality_estimate(&self) -> usize {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let harmonic: f64 = self
+            .registers
+            .iter()
+            .map(|r| 2.0_f64.powi(-(*r as i32)))
+            .sum();
+        let estimate = alpha * m * m / harmonic;
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+
+    /// The standard error of `cardinality_estimate()`, relative to the true cardinality.
+    pub fn relative_error(&self) -> f64 {
+        1.04 / (self.registers.len() as f64).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let mut counter = ApproxLabelCounter::new(10);
+        assert_eq!(counter.cardinality_estimate(), 0);
+        for i in 0..50 {
+            counter.insert(&i);
+            counter.insert(&i);
+        }
+        let small = counter.cardinality_estimate() as f64;
+        assert!((small - 50.0).abs() < 5.0);
+
+        for i in 0..100_000 {
+            counter.insert(&i);
+        }
+        let large = counter.cardinality_estimate() as f64;
+        assert!((large - 100_000.0).abs() < 100_000.0 * 4.0 * counter.relative_error());
+    }
+}
@@ -0,0 +1,213 @@
+//! # Clustering histograms
+//! `cluster_histograms()` groups histograms with similar distributions using k-medoids, so that,
+//! for example, users can be grouped by their histograms of event types. Each cluster is
+//! represented by its medoid, the member with the smallest total distance to the other members.
+//! Clustering is deterministic: initial medoids are chosen greedily, as in the BUILD step of
+//! Partitioning Around Medoids, and then refined until no assignment changes.
+//!
+//! ```
+//! use hash_histogram::{cluster_histograms, HashHistogram, Metric};
+//!
+//! let users: Vec<HashHistogram<&str>> = [
+//!     vec!["view", "view", "view", "buy"],
+//!     vec!["view", "view", "buy"],
+//!     vec!["post", "post", "comment"],
+//!     vec!["post", "comment", "comment", "post"],
+//! ]
+//! .iter()
+//! .map(|events| events.iter().collect())
+//! .collect();
+//! let clusters = cluster_histograms(&users, 2, Metric::JensenShannon);
+//! assert_eq!(clusters.assignments[0], clusters.assignments[1]);
+//! assert_eq!(clusters.assignments[2], clusters.assignments[3]);
+//! assert_ne!(clusters.assignments[0], clusters.assignments[2]);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+
+/// A distance between the distributions of two histograms. Both range from 0, for identical
+/// distributions, to 1.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Metric {
+    /// The Jensen-Shannon divergence, in bits.
+    JensenShannon,
+    /// One minus the cosine similarity of the count vectors.
+    Cosine,
+}
+
+impl Metric {
+    /// An empty histogram is at distance 0 from another empty histogram and 1 from any other.
+    pub fn distance<T: KeyType, C: CounterType>(
+        &self,
+        a: &HashHistogram<T, C>,
+        b: &HashHistogram<T, C>,
+    ) -> f64 {
+        let (total_a, total_b) = (total(a), total(b));
+        if total_a == 0.0 || total_b == 0.0 {
+            return if total_a == total_b { 0.0 } else { 1.0 };
+        }
+        let pairs = a
+            .iter()
+            .map(|(key, count)| (count.to_f64().unwrap(), b.count(key).to_f64().unwrap()))
+            .chain(
+                b.iter()
+                    .filter(|(key, _)| !a.contains(*key))
+                    .map(|(_, count)| (0.0, count.to_f64().unwrap())),
+            );
+        match self {
+            Metric::JensenShannon => {
+                let half_kl = |p: f64, m: f64| {
+                    if p > 0.0 {
+                        p * (p / m).log2() / 2.0
+                    } else {
+                        0.0
+                    }
+                };
+                let divergence: f64 = pairs
+                    .map(|(x, y)| {
+                        let (p, q) = (x / total_a, y / total_b);
+                        let m = (p + q) / 2.0;
+                        half_kl(p, m) + half_kl(q, m)
+                    })
+                    .sum();
+                divergence.clamp(0.0, 1.0)
+            }
+            Metric::Cosine => {
+                let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+                for (x, y) in pairs {
+                    dot += x * y;
+                    norm_a += x * x;
+                    norm_b += y * y;
+                }
+                (1.0 - dot / (norm_a.sqrt() * norm_b.sqrt())).clamp(0.0, 1.0)
+            }
+        }
+    }
+}
+
+fn total<T: KeyType, C: CounterType>(histogram: &HashHistogram<T, C>) -> f64 {
+    histogram.total_count().to_f64().unwrap()
+}
+
+/// The result of `cluster_histograms()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clustering {
+    /// The cluster of each histogram, as an index into `medoids`.
+    pub assignments: Vec<usize>,
+    /// The index of the histogram at the center of each cluster.
+    pub medoids: Vec<usize>,
+    /// The sum of the distances from each histogram to its medoid.
+    pub cost: f64,
+}
+
+/// Groups `items` into at most `k` clusters. Fewer clusters are produced if there are fewer than
+/// `k` histograms. Panics if `k` is zero.
+pub fn cluster_histograms<T: KeyType, C: CounterType>(
+    items: &[HashHistogram<T, C>],
+    k: usize,
+    metric: Metric,
+) -> Clustering {
+    assert!(k > 0, "k must be positive");
+    let n = items.len();
+    let distances: Vec<Vec<f64>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| metric.distance(&items[i], &items[j]))
+                .collect()
+        })
+        .collect();
+    let cost_of = |medoids: &[usize]| -> f64 {
+        (0..n)
+            .map(|i| {
+                medoids
+                    .iter()
+                    .map(|m| distances[i][*m])
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .sum()
+    };
+
+    let mut medoids: Vec<usize> = Vec::new();
+    while medoids.len() < k.min(n) {
+        let cost_with = |candidate: usize| cost_of(&[medoids.as_slice(), &[candidate]].concat());
+        let best = (0..n)
+            .filter(|i| !medoids.contains(i))
+            .map(|i| (i, cost_with(i)))
+            .min_by(|(_, c1), (_, c2)| c1.total_cmp(c2))
+            .map(|(i, _)| i)
+            .unwrap();
+        medoids.push(best);
+    }
+
+    let nearest = |i: usize, medoids: &[usize]| -> usize {
+        (0..medoids.len())
+            .min_by(|a, b| distances[i][medoids[*a]].total_cmp(&distances[i][medoids[*b]]))
+            .unwrap()
+    };
+    let mut assignments: Vec<usize> = (0..n).map(|i| nearest(i, &medoids)).collect();
+    loop {
+        for (cluster, medoid) in medoids.iter_mut().enumerate() {
+            let members: Vec<usize> = (0..n).filter(|i| assignments[*i] == cluster).collect();
+            let within = |candidate: usize| -> f64 {
+                members.iter().map(|i| distances[candidate][*i]).sum()
+            };
+            if let Some(best) = members
+                .iter()
+                .copied()
+                .min_by(|a, b| within(*a).total_cmp(&within(*b)))
+            {
+                if within(best) < within(*medoid) {
+                    *medoid = best;
+                }
+            }
+        }
+        let updated: Vec<usize> = (0..n).map(|i| nearest(i, &medoids)).collect();
+        if updated == assignments {
+            break;
+        }
+        assignments = updated;
+    }
+    Clustering {
+        cost: cost_of(&medoids),
+        assignments,
+        medoids,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let a: HashHistogram<char> = "aab".chars().collect();
+        let scaled = a.clone() * 3;
+        let disjoint: HashHistogram<char> = "cd".chars().collect();
+        let empty = HashHistogram::new();
+        for metric in [Metric::JensenShannon, Metric::Cosine] {
+            assert!(metric.distance(&a, &scaled).abs() < 1e-12);
+            assert!((metric.distance(&a, &disjoint) - 1.0).abs() < 1e-12);
+            assert_eq!(metric.distance(&a, &empty), 1.0);
+            assert_eq!(metric.distance(&empty, &empty), 0.0);
+        }
+        let b: HashHistogram<char> = "ab".chars().collect();
+        let cosine = 1.0 - 3.0 / (5.0f64.sqrt() * 2.0f64.sqrt());
+        assert!((Metric::Cosine.distance(&a, &b) - cosine).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_clusters() {
+        let items: Vec<HashHistogram<u8>> = (0..9u8)
+            .map(|i| [i % 3, i % 3, i % 3, 10 + i].iter().collect())
+            .collect();
+        let clustering = cluster_histograms(&items, 3, Metric::Cosine);
+        assert_eq!(clustering.medoids.len(), 3);
+        for i in 0..9 {
+            assert_eq!(clustering.assignments[i], clustering.assignments[i % 3]);
+            assert_eq!(clustering.medoids[clustering.assignments[i]] % 3, i % 3);
+        }
+        let single = cluster_histograms(&items[..2], 5, Metric::JensenShannon);
+        assert_eq!(single.assignments, vec![0, 1]);
+        assert_eq!(single.cost, 0.0);
+    }
+}
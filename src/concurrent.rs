@@ -0,0 +1,160 @@
+//! # Concurrent histograms
+//! `ConcurrentHashHistogram` can be bumped from many threads at once through a shared reference,
+//! without an external mutex. Keys are spread across independently locked shards, and counts are
+//! atomic, so bumping a key that is already present only takes a shared lock on its shard.
+//!
+//! ```
+//! use hash_histogram::ConcurrentHashHistogram;
+//!
+//! let h = ConcurrentHashHistogram::new();
+//! std::thread::scope(|s| {
+//!     for _ in 0..4 {
+//!         s.spawn(|| {
+//!             for word in ["a", "b", "a"].iter() {
+//!                 h.bump(word);
+//!             }
+//!         });
+//!     }
+//! });
+//! let snapshot = h.snapshot();
+//! assert_eq!(snapshot.count(&"a"), 8);
+//! assert_eq!(snapshot.count(&"b"), 4);
+//! ```
+
+use crate::{HashHistogram, KeyType};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+#[derive(Debug)]
+pub struct ConcurrentHashHistogram<T: KeyType> {
+    shards: Vec<RwLock<HashMap<T, AtomicUsize>>>,
+}
+
+impl<T: KeyType> Default for ConcurrentHashHistogram<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: KeyType> ConcurrentHashHistogram<T> {
+    /// Creates a histogram with four shards per available CPU.
+    pub fn new() -> Self {
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards(cpus * 4)
+    }
+
+    /// Creates a histogram with the given number of shards. Panics if `shards` is zero.
+    pub fn with_shards(shards: usize) -> Self {
+        assert!(shards > 0, "at least one shard is required");
+        ConcurrentHashHistogram {
+            shards: (0..shards).map(|_| RwLock::new(HashMap::new())).collect(),
+        }
+    }
+
+    pub fn bump(&self, item: &T) {
+        self.bump_by(item, 1);
+    }
+
+    pub fn bump_by(&self, item: &T, increment: usize) {
+        let shard = self.shard(item);
+        if let Some(count) = shard.read().unwrap().get(item) {
+            count.fetch_add(increment, Ordering::Relaxed);
+            return;
+        }
+        shard
+            .write()
+            .unwrap()
+            .entry(item.clone())
+            .or_default()
+            .fetch_add(increment, Ordering::Relaxed);
+    }
+
+    pub fn count(&self, item: &T) -> usize {
+        self.shard(item)
+            .read()
+            .unwrap()
+            .get(item)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.read().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|s| s.read().unwrap().is_empty())
+    }
+
+    pub fn total_count(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| {
+                s.read()
+                    .unwrap()
+                    .values()
+                    .map(|c| c.load(Ordering::Relaxed))
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Copies the current counts into a `HashHistogram`. Shards are read one at a time, so bumps
+    /// made while the snapshot is in progress may or may not be included.
+    pub fn snapshot(&self) -> HashHistogram<T> {
+        let mut result = HashHistogram::new();
+        for shard in self.shards.iter() {
+            for (key, count) in shard.read().unwrap().iter() {
+                result.bump_by(key, count.load(Ordering::Relaxed));
+            }
+        }
+        result
+    }
+
+    pub fn into_histogram(self) -> HashHistogram<T> {
+        let mut result = HashHistogram::new();
+        for shard in self.shards {
+            for (key, count) in shard.into_inner().unwrap() {
+                result.bump_by(&key, count.into_inner());
+            }
+        }
+        result
+    }
+
+    fn shard(&self, item: &T) -> &RwLock<HashMap<T, AtomicUsize>> {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        &self.shards[(hasher.finish() % self.shards.len() as u64) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_bumps() {
+        let h = ConcurrentHashHistogram::with_shards(3);
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let h = &h;
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        h.bump(&(i % 10));
+                        h.bump_by(&(100 + t), 2);
+                    }
+                });
+            }
+        });
+        assert_eq!(h.len(), 18);
+        assert_eq!(h.count(&3), 800);
+        assert_eq!(h.count(&105), 2000);
+        assert_eq!(h.count(&99), 0);
+        assert_eq!(h.total_count(), 24000);
+        let snapshot = h.snapshot();
+        assert_eq!(snapshot.total_count(), 24000);
+        assert_eq!(h.into_histogram(), snapshot);
+    }
+}
@@ -0,0 +1,83 @@
+//! # Converting counter types
+//! `convert_counts()` copies a histogram into one with a different counter type, checking that
+//! every count fits, so a compact `u32` histogram can be narrowed or widened as needed. When the
+//! target type can represent every count of the source type, `widen_counts()` converts without
+//! the possibility of failure.
+//!
+//! ```
+//! use hash_histogram::{HashHistogram, OverflowError};
+//!
+//! let mut h = HashHistogram::<&str, u64>::new();
+//! h.bump_by(&"small", 200);
+//! let narrow: HashHistogram<&str, u8> = h.convert_counts().unwrap();
+//! assert_eq!(narrow.count(&"small"), 200);
+//!
+//! h.bump_by(&"large", 1000);
+//! assert_eq!(h.convert_counts::<u8>(), Err(OverflowError { key: "large" }));
+//!
+//! let wide: HashHistogram<&str, u128> = narrow.widen_counts();
+//! assert_eq!(wide.total_count(), 200);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::fmt::{self, Debug};
+
+/// A conversion rejected because the count of `key` does not fit in the target counter type.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OverflowError<T> {
+    pub key: T,
+}
+
+impl<T: Debug> fmt::Display for OverflowError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "count of {:?} does not fit in the target counter type",
+            self.key
+        )
+    }
+}
+
+impl<T: Debug> std::error::Error for OverflowError<T> {}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    pub fn convert_counts<C2: CounterType>(
+        &self,
+    ) -> Result<HashHistogram<T, C2>, OverflowError<T>> {
+        let mut result = HashHistogram::new();
+        for (key, count) in self.iter() {
+            let converted = <C2 as num::NumCast>::from(*count)
+                .ok_or_else(|| OverflowError { key: key.clone() })?;
+            result.bump_by(key, converted);
+        }
+        Ok(result)
+    }
+
+    pub fn widen_counts<C2: CounterType + From<C>>(&self) -> HashHistogram<T, C2> {
+        let mut result = HashHistogram::new();
+        for (key, count) in self.iter() {
+            result.bump_by(key, <C2 as From<C>>::from(*count));
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_keep_zero_counts() {
+        let h = HashHistogram::<char, usize>::with_labels(['z']);
+        let converted: HashHistogram<char, u16> = h.convert_counts().unwrap();
+        assert!(converted.contains(&'z'));
+        let mut big = HashHistogram::<u8, u128>::new();
+        big.bump_by(&1, u64::MAX as u128 + 1);
+        let err = big.convert_counts::<u64>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "count of 1 does not fit in the target counter type"
+        );
+        assert_eq!(big.widen_counts::<u128>(), big);
+    }
+}
@@ -0,0 +1,195 @@
+//! # Count-Min Sketch
+//! `CountMinSketch` approximates the counts of a `HashHistogram` in a fixed amount of memory.
+//! It shares the `bump()`, `bump_by()`, and `count()` vocabulary of `HashHistogram`, so it can
+//! replace one when memory is tight. Counts are never underestimated; with probability at least
+//! `1 - delta`, each is overestimated by at most `epsilon * total_count()`.
+//!
+//! ```
+//! use hash_histogram::{CountMinSketch, HashHistogram};
+//!
+//! let mut sketch = CountMinSketch::<&str>::with_error(0.01, 0.01);
+//! for s in ["a", "b", "a", "c", "a"].iter() {
+//!     sketch.bump(s);
+//! }
+//! assert!(sketch.count(&"a") >= 3);
+//!
+//! // Converting from exact counts, and back for the keys of interest
+//! let exact: HashHistogram<&str> = ["x", "y", "x"].iter().collect();
+//! let sketch = CountMinSketch::from_histogram(&exact, 100, 4);
+//! let estimated = sketch.to_histogram(exact.labels());
+//! assert!(estimated.count(&"x") >= 2);
+//! ```
+//!
+//! Keys are hashed with `std::collections::hash_map::DefaultHasher`, whose algorithm may change
+//! between Rust releases. Serialized sketches should be read back by programs built with the
+//! same toolchain.
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CountMinSketch<T: KeyType, C: CounterType = usize> {
+    width: usize,
+    depth: usize,
+    table: Vec<C>,
+    total: C,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    keys: PhantomData<fn(&T)>,
+}
+
+impl<T: KeyType, C: CounterType> CountMinSketch<T, C> {
+    /// Creates a sketch with `depth` rows of `width` counters. Panics if either is zero.
+    pub fn new(width: usize, depth: usize) -> Self {
+        assert!(width > 0 && depth > 0, "width and depth must be positive");
+        let cells = width
+            .checked_mul(depth)
+            .expect("sketch dimensions are too large");
+        CountMinSketch {
+            width,
+            depth,
+            table: vec![C::default(); cells],
+            total: C::default(),
+            keys: PhantomData,
+        }
+    }
+
+    /// Sizes the sketch so that, with probability at least `1 - delta`, each count exceeds its
+    /// true value by at most `epsilon * total_count()`. Panics unless `epsilon` is positive and
+    /// `delta` lies strictly between 0 and 1.
+    pub fn with_error(epsilon: f64, delta: f64) -> Self {
+        assert!(epsilon > 0.0, "epsilon must be positive");
+        assert!(delta > 0.0 && delta < 1.0, "delta must be between 0 and 1");
+        let width = (std::f64::consts::E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil() as usize;
+        Self::new(width, depth.max(1))
+    }
+
+    pub fn from_histogram(histogram: &HashHistogram<T, C>, width: usize, depth: usize) -> Self {
+        let mut result = Self::new(width, depth);
+        for (key, count) in histogram.iter() {
+            result.bump_by(key, *count);
+        }
+        result
+    }
+
+    /// Builds a histogram of the estimated counts of `keys`, omitting those estimated at zero.
+    pub fn to_histogram<'a, I: IntoIterator<Item = &'a T>>(&self, keys: I) -> HashHistogram<T, C>
+    where
+        T: 'a,
+    {
+        let mut result = HashHistogram::new();
+        for key in keys {
+            let count = self.count(key);
+            if count > C::default() {
+                result.bump_by(key, count);
+            }
+        }
+        result
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, num::one());
+    }
+
+    pub fn bump_by(&mut self, item: &T, increment: C) {
+        self.total += increment;
+        for row in 0..self.depth {
+            let cell = self.cell(row, item);
+            self.table[cell] += increment;
+        }
+    }
+
+    /// An estimate of the count of `item` that is never less than its true count.
+    pub fn count(&self, item: &T) -> C {
+        (0..self.depth)
+            .map(|row| self.table[self.cell(row, item)])
+            .min()
+            .unwrap()
+    }
+
+    pub fn total_count(&self) -> C {
+        self.total
+    }
+
+    /// Adds the counts of `other` to this sketch. Panics if the dimensions differ.
+    pub fn merge(&mut self, other: &Self) {
+        assert!(
+            self.width == other.width && self.depth == other.depth,
+            "sketch dimensions must match"
+        );
+        for (mine, theirs) in self.table.iter_mut().zip(other.table.iter()) {
+            *mine += *theirs;
+        }
+        self.total += other.total;
+    }
+
+    fn cell(&self, row: usize, item: &T) -> usize {
+        let mut hasher = DefaultHasher::new();
+        row.hash(&mut hasher);
+        item.hash(&mut hasher);
+        row * self.width + (hasher.finish() % self.width as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_never_underestimates() {
+        let mut exact = HashHistogram::<u32>::new();
+        let mut sketch = CountMinSketch::<u32>::new(50, 4);
+        for i in 0..2000u32 {
+            let key = i % 97 * (i % 5);
+            exact.bump(&key);
+            sketch.bump(&key);
+        }
+        assert_eq!(sketch.total_count(), exact.total_count());
+        for (key, count) in exact.iter() {
+            assert!(sketch.count(key) >= *count);
+        }
+        let estimated = sketch.to_histogram(exact.labels());
+        assert_eq!(estimated.len(), exact.len());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = CountMinSketch::<&str, u64>::with_error(0.1, 0.1);
+        let mut b = a.clone();
+        a.bump_by(&"x", 3);
+        b.bump_by(&"x", 4);
+        b.bump(&"y");
+        a.merge(&b);
+        assert_eq!(a.total_count(), 8);
+        assert!(a.count(&"x") >= 7);
+        assert_eq!((a.width(), a.depth()), (28, 3));
+    }
+
+    #[test]
+    fn test_invalid_error_bounds() {
+        for (epsilon, delta) in [
+            (0.0, 0.1),
+            (-1.0, 0.1),
+            (0.1, 0.0),
+            (0.1, 1.0),
+            (1e-300, 0.1),
+        ] {
+            let result =
+                std::panic::catch_unwind(|| CountMinSketch::<u8>::with_error(epsilon, delta));
+            assert!(result.is_err());
+        }
+    }
+}
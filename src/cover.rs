@@ -0,0 +1,86 @@
+//! # Covering keys
+//! `min_keys_covering()` chooses a small set of keys that together account for at least a target
+//! fraction of the total count of every one of several histograms, as when choosing a compact
+//! allowlist of metrics to monitor across many hosts. Each histogram counts equally, whatever its
+//! size. Keys are chosen greedily, as in the greedy algorithm for set cover: each step adds the
+//! key that most reduces the remaining shortfall, summed over the histograms.
+//!
+//! ```
+//! use hash_histogram::{min_keys_covering, HashHistogram};
+//!
+//! let web = HashHistogram::<&str>::from_iter([("GET", 80), ("POST", 15), ("PUT", 5)]);
+//! let api = HashHistogram::<&str>::from_iter([("POST", 70), ("GET", 20), ("DELETE", 10)]);
+//! assert_eq!(min_keys_covering(&[&web, &api], 0.8), vec!["GET", "POST"]);
+//! assert_eq!(min_keys_covering(&[&web, &api], 0.2), vec!["GET"]);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::BTreeSet;
+
+/// Shortfalls below this are treated as covered, absorbing rounding error.
+const TOLERANCE: f64 = 1e-12;
+
+/// Returns the chosen keys in the order they were chosen. Ties are broken in favor of the
+/// smaller key. If even every key together falls short, as for a `fraction` above 1, every key
+/// that helps is returned. Empty histograms need no keys.
+pub fn min_keys_covering<T: KeyType + Ord, C: CounterType>(
+    histograms: &[&HashHistogram<T, C>],
+    fraction: f64,
+) -> Vec<T> {
+    let totals: Vec<f64> = histograms
+        .iter()
+        .map(|h| h.total_count().to_f64().unwrap())
+        .collect();
+    let share = |i: usize, key: &T| {
+        if totals[i] > 0.0 {
+            histograms[i].count(key).to_f64().unwrap() / totals[i]
+        } else {
+            0.0
+        }
+    };
+    let mut remaining: Vec<f64> = totals
+        .iter()
+        .map(|total| if *total > 0.0 { fraction } else { 0.0 })
+        .collect();
+    let mut candidates: BTreeSet<&T> = histograms.iter().flat_map(|h| h.keys()).collect();
+    let mut chosen = Vec::new();
+    while remaining.iter().any(|r| *r > TOLERANCE) {
+        let gain = |key: &T| -> f64 {
+            (0..histograms.len())
+                .map(|i| share(i, key).min(remaining[i].max(0.0)))
+                .sum()
+        };
+        let best = candidates.iter().map(|key| (*key, gain(key))).fold(
+            None,
+            |best: Option<(&T, f64)>, (key, g)| match best {
+                Some((_, best_gain)) if best_gain >= g => best,
+                _ => Some((key, g)),
+            },
+        );
+        let Some((key, _)) = best.filter(|(_, g)| *g > 0.0) else {
+            break;
+        };
+        for (i, r) in remaining.iter_mut().enumerate() {
+            *r -= share(i, key);
+        }
+        candidates.remove(key);
+        chosen.push(key.clone());
+    }
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_cases() {
+        let a: HashHistogram<u8> = [1, 1, 2, 3].iter().collect();
+        let empty = HashHistogram::new();
+        assert_eq!(min_keys_covering(&[&a, &empty], 0.5), vec![1]);
+        assert_eq!(min_keys_covering(&[&a, &empty], 0.0), Vec::<u8>::new());
+        assert_eq!(min_keys_covering(&[&a], 1.0), vec![1, 2, 3]);
+        assert_eq!(min_keys_covering(&[&a], 2.0), vec![1, 2, 3]);
+        assert_eq!(min_keys_covering::<u8, usize>(&[], 0.5), Vec::<u8>::new());
+    }
+}
@@ -0,0 +1,301 @@
+//! # CSV import and export
+//! `to_csv()` writes a histogram as one `key,count` line per key, in key order, and `from_csv()`
+//! reads it back. `CsvOptions` selects the delimiter, such as tabs for TSV, and whether there is
+//! a header line. Fields containing the delimiter, quotes, or line breaks are quoted, and a quoted
+//! field may continue across lines.
+//!
+//! ```
+//! use hash_histogram::{CsvOptions, HashHistogram};
+//!
+//! let h: HashHistogram<String> = ["b", "a", "b"].iter().map(|s| s.to_string()).collect();
+//! let mut out = Vec::new();
+//! h.to_csv(&mut out).unwrap();
+//! assert_eq!(String::from_utf8(out.clone()).unwrap(), "a,1\nb,2\n");
+//! assert_eq!(HashHistogram::from_csv(out.as_slice()).unwrap(), h);
+//!
+//! let tsv = "word\tcount\nthe\t12\ncat\t3\n";
+//! let parsed: HashHistogram<String> =
+//!     HashHistogram::from_csv_with(tsv.as_bytes(), &CsvOptions::tsv()).unwrap();
+//! assert_eq!(parsed.count(&"the".to_string()), 12);
+//! ```
+//!
+//! `export_wide_csv()` lines up several named histograms, writing one row per key and one
+//! column per histogram, so that runs can be compared side by side in a spreadsheet. Keys
+//! missing from a histogram get a count of 0. Rows are ordered by key.
+//!
+//! ```
+//! use hash_histogram::{export_wide_csv, HashHistogram};
+//!
+//! let monday: HashHistogram<&str> = ["tea", "coffee", "coffee"].iter().collect();
+//! let tuesday: HashHistogram<&str> = ["coffee", "juice"].iter().collect();
+//!
+//! let mut out = Vec::new();
+//! export_wide_csv(&[("monday", &monday), ("tuesday", &tuesday)], &mut out).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(out).unwrap(),
+//!     "key,monday,tuesday\ncoffee,2,1\njuice,0,1\ntea,1,0\n"
+//! );
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::str::FromStr;
+
+/// Formatting of the files read by `from_csv_with()` and written by `to_csv_with()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    /// Whether the first line is a header, which is skipped when reading. The header written
+    /// names the columns `key` and `count`.
+    pub headers: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            headers: false,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Tab-separated values with a header line.
+    pub fn tsv() -> Self {
+        CsvOptions {
+            delimiter: '\t',
+            headers: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// A malformed line, numbered from 1.
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(e) => write!(f, "{}", e),
+            CsvError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<io::Error> for CsvError {
+    fn from(e: io::Error) -> Self {
+        CsvError::Io(e)
+    }
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    pub fn to_csv<W: Write>(&self, writer: W) -> io::Result<()>
+    where
+        T: Ord + Display,
+        C: Display,
+    {
+        self.to_csv_with(writer, &CsvOptions::default())
+    }
+
+    pub fn to_csv_with<W: Write>(&self, mut writer: W, options: &CsvOptions) -> io::Result<()>
+    where
+        T: Ord + Display,
+        C: Display,
+    {
+        let d = options.delimiter;
+        if options.headers {
+            writeln!(writer, "key{}count", d)?;
+        }
+        let mut entries: Vec<(&T, &C)> = self.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        for (key, count) in entries {
+            writeln!(writer, "{}{}{}", escape(&key.to_string(), d), d, count)?;
+        }
+        Ok(())
+    }
+
+    pub fn from_csv<R: Read>(reader: R) -> Result<Self, CsvError>
+    where
+        T: FromStr,
+        C: FromStr,
+    {
+        Self::from_csv_with(reader, &CsvOptions::default())
+    }
+
+    /// Counts for repeated keys are added together. Blank lines are skipped. Errors report the
+    /// line on which the malformed record begins.
+    pub fn from_csv_with<R: Read>(reader: R, options: &CsvOptions) -> Result<Self, CsvError>
+    where
+        T: FromStr,
+        C: FromStr,
+    {
+        let mut result = HashHistogram::new();
+        let mut reader = BufReader::new(reader);
+        let mut lines_read = 0;
+        let mut skip_header = options.headers;
+        loop {
+            let first_line = lines_read + 1;
+            let parse_error = |message: &str| CsvError::Parse {
+                line: first_line,
+                message: message.to_string(),
+            };
+            let mut record = String::new();
+            let fields = loop {
+                if reader.read_line(&mut record)? == 0 {
+                    if record.is_empty() {
+                        return Ok(result);
+                    }
+                    return Err(parse_error("unclosed quote"));
+                }
+                lines_read += 1;
+                let line = record.strip_suffix('\n').unwrap_or(&record);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                if let Some(fields) = split(line, options.delimiter) {
+                    break fields;
+                }
+            };
+            if skip_header {
+                skip_header = false;
+                continue;
+            }
+            if record.trim().is_empty() {
+                continue;
+            }
+            if fields.len() != 2 {
+                return Err(parse_error("expected two fields"));
+            }
+            let key = fields[0].parse().map_err(|_| parse_error("invalid key"))?;
+            let count = fields[1]
+                .trim()
+                .parse()
+                .map_err(|_| parse_error("invalid count"))?;
+            result
+                .count(&key)
+                .checked_add(&count)
+                .ok_or_else(|| parse_error("count overflows"))?;
+            result.bump_by(&key, count);
+        }
+    }
+}
+
+pub fn export_wide_csv<T, C, W>(
+    histograms: &[(&str, &HashHistogram<T, C>)],
+    mut writer: W,
+) -> io::Result<()>
+where
+    T: KeyType + Ord + Display,
+    C: CounterType + Display,
+    W: Write,
+{
+    write!(writer, "key")?;
+    for (name, _) in histograms {
+        write!(writer, ",{}", escape(name, ','))?;
+    }
+    writeln!(writer)?;
+
+    let keys: BTreeSet<&T> = histograms
+        .iter()
+        .flat_map(|(_, h)| h.iter().map(|(key, _)| key))
+        .collect();
+    for key in keys {
+        write!(writer, "{}", escape(&key.to_string(), ','))?;
+        for (_, h) in histograms {
+            write!(writer, ",{}", h.count(key))?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Quotes a field if it contains the delimiter, a quote, or a line break.
+fn escape(field: &str, delimiter: char) -> String {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits a line into fields, removing quotes. Returns `None` if a quote is left open.
+fn split(line: &str, delimiter: char) -> Option<Vec<String>> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    (!quoted).then_some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escaping() {
+        let mut h = HashHistogram::<&str>::new();
+        h.bump(&"a,b");
+        h.bump_by(&"say \"hi\"", 3);
+        let mut out = Vec::new();
+        export_wide_csv(&[("run 1", &h), ("run,2", &HashHistogram::new())], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "key,run 1,\"run,2\"\n\"a,b\",1,0\n\"say \"\"hi\"\"\",3,0\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_with_quoting() {
+        let mut h = HashHistogram::<String, u64>::new();
+        h.bump_by(&"tab\there".to_string(), 2);
+        h.bump_by(&"\"quoted\"".to_string(), 7);
+        h.bump_by(&"two\nlines".to_string(), 1);
+        h.bump_by(&"crlf\r\n".to_string(), 4);
+        let options = CsvOptions::tsv();
+        let mut out = Vec::new();
+        h.to_csv_with(&mut out, &options).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("key\tcount\n"));
+        let parsed = HashHistogram::from_csv_with(text.as_bytes(), &options).unwrap();
+        assert_eq!(parsed, h);
+
+        let summed: HashHistogram<String, u64> =
+            HashHistogram::from_csv("a,1\n\na,2\n".as_bytes()).unwrap();
+        assert_eq!(summed.count(&"a".to_string()), 3);
+        match HashHistogram::<String, u64>::from_csv("a,1\nb,x\n".as_bytes()) {
+            Err(CsvError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("unexpected result {:?}", other),
+        }
+        match HashHistogram::<String, u64>::from_csv("a,1\n\"b\nc,2\n".as_bytes()) {
+            Err(CsvError::Parse { line, message }) => {
+                assert_eq!((line, message.as_str()), (2, "unclosed quote"))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        match HashHistogram::<String, u8>::from_csv("a,200\na,100\n".as_bytes()) {
+            Err(CsvError::Parse { line, message }) => {
+                assert_eq!((line, message.as_str()), (2, "count overflows"))
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! # Decaying histograms
+//! In a `DecayingHistogram`, every observation loses half of its weight per half-life, so the
+//! counts track recently popular keys. Decay is applied lazily: each observation is stored with a
+//! weight that grows with its timestamp, and counts are scaled down when read. Since this scales
+//! all keys equally, `ranking()` and `mode()` need no timestamp.
+//!
+//! ```
+//! use hash_histogram::DecayingHistogram;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut popular = DecayingHistogram::new(Duration::from_secs(60));
+//! let start = Instant::now();
+//! for _ in 0..4 {
+//!     popular.bump_at(&"old news", start);
+//! }
+//! popular.bump_at(&"trending", start + Duration::from_secs(120));
+//! popular.bump_at(&"trending", start + Duration::from_secs(120));
+//!
+//! let now = start + Duration::from_secs(120);
+//! assert_eq!(popular.count_at(&"old news", now), 1.0);
+//! assert_eq!(popular.count_at(&"trending", now), 2.0);
+//! assert_eq!(popular.mode(), Some("trending"));
+//! ```
+
+use crate::KeyType;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Stored weights are rescaled once they have grown by this many half-lives, long before `f64`
+/// could overflow.
+const MAX_HALF_LIVES: f64 = 256.0;
+
+#[derive(Debug, Clone)]
+pub struct DecayingHistogram<T: KeyType> {
+    half_life: Duration,
+    origin: Instant,
+    weights: HashMap<T, f64>,
+}
+
+impl<T: KeyType> DecayingHistogram<T> {
+    /// Panics if `half_life` is zero.
+    pub fn new(half_life: Duration) -> Self {
+        assert!(!half_life.is_zero(), "half-life must be positive");
+        DecayingHistogram {
+            half_life,
+            origin: Instant::now(),
+            weights: HashMap::new(),
+        }
+    }
+
+    pub fn half_life(&self) -> Duration {
+        self.half_life
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_at(item, Instant::now());
+    }
+
+    pub fn bump_at(&mut self, item: &T, time: Instant) {
+        self.bump_by_at(item, 1.0, time);
+    }
+
+    /// Records an observation of `item` with the given weight at `time`.
+    pub fn bump_by_at(&mut self, item: &T, weight: f64, time: Instant) {
+        if self.weights.is_empty() {
+            self.origin = time;
+        } else if self.half_lives_since_origin(time) > MAX_HALF_LIVES {
+            self.rebase(time);
+        }
+        let scaled = weight * self.half_lives_since_origin(time).exp2();
+        *self.weights.entry(item.clone()).or_insert(0.0) += scaled;
+    }
+
+    pub fn count(&self, item: &T) -> f64 {
+        self.count_at(item, Instant::now())
+    }
+
+    /// The decayed weight of `item` as of `now`.
+    pub fn count_at(&self, item: &T, now: Instant) -> f64 {
+        self.weights.get(item).copied().unwrap_or(0.0) * self.decay_factor(now)
+    }
+
+    pub fn total_count_at(&self, now: Instant) -> f64 {
+        self.weights.values().sum::<f64>() * self.decay_factor(now)
+    }
+
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    pub fn ranking(&self) -> Vec<T> {
+        let mut ranking: Vec<(&T, f64)> = self.weights.iter().map(|(k, w)| (k, *w)).collect();
+        ranking.sort_by(|(_, w1), (_, w2)| w2.total_cmp(w1));
+        ranking.into_iter().map(|(k, _)| k.clone()).collect()
+    }
+
+    pub fn mode(&self) -> Option<T> {
+        self.weights
+            .iter()
+            .max_by(|(_, w1), (_, w2)| w1.total_cmp(w2))
+            .map(|(k, _)| k.clone())
+    }
+
+    /// Removes keys whose decayed weight as of `now` is below `min_weight`, bounding memory use
+    /// by forgetting keys that are no longer observed.
+    pub fn prune(&mut self, min_weight: f64, now: Instant) {
+        let threshold = min_weight / self.decay_factor(now);
+        self.weights.retain(|_, weight| *weight >= threshold);
+    }
+
+    fn half_lives_since_origin(&self, time: Instant) -> f64 {
+        let seconds = if time >= self.origin {
+            time.duration_since(self.origin).as_secs_f64()
+        } else {
+            -self.origin.duration_since(time).as_secs_f64()
+        };
+        seconds / self.half_life.as_secs_f64()
+    }
+
+    fn decay_factor(&self, now: Instant) -> f64 {
+        (-self.half_lives_since_origin(now)).exp2()
+    }
+
+    /// Moves the origin to `time`, rescaling the stored weights to match.
+    fn rebase(&mut self, time: Instant) {
+        let factor = self.decay_factor(time);
+        self.weights.retain(|_, weight| {
+            *weight *= factor;
+            *weight > 0.0
+        });
+        self.origin = time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_and_prune() {
+        let start = Instant::now();
+        let half_life = Duration::from_secs(1);
+        let mut h = DecayingHistogram::new(half_life);
+        h.bump_at(&'a', start);
+        let later = start + half_life * 300;
+        h.bump_by_at(&'b', 3.0, later);
+        h.bump_at(&'c', later);
+        assert_eq!(h.len(), 3);
+        assert_eq!(h.count_at(&'b', later + half_life), 1.5);
+        assert_eq!(h.ranking(), vec!['b', 'c', 'a']);
+        h.prune(1e-6, later);
+        assert_eq!(h.len(), 2);
+        assert!((h.total_count_at(later) - 4.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,132 @@
+//! # Moving averages of incidence
+//! `EmaHistogram` keeps an exponential moving average of how often each key occurs. Every bump
+//! is one step: the bumped key's average moves toward 1 and every other key's average decays
+//! toward 0, each by the smoothing factor `alpha`. The averages of all keys sum to at most 1, and
+//! approximate the share of recent bumps that went to each key. Other keys are decayed lazily,
+//! so a bump takes constant time.
+//!
+//! ```
+//! use hash_histogram::EmaHistogram;
+//!
+//! let mut trend = EmaHistogram::new(0.5);
+//! trend.bump(&"old");
+//! trend.bump(&"old");
+//! trend.bump(&"new");
+//! assert_eq!(trend.ema(&"old"), 0.375);
+//! assert_eq!(trend.ema(&"new"), 0.5);
+//! assert_eq!(trend.ranking(), vec!["new", "old"]);
+//! ```
+
+use crate::KeyType;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EmaHistogram<T: KeyType> {
+    alpha: f64,
+    steps: u64,
+    /// The average of each key as of the step at which it was last bumped.
+    averages: HashMap<T, (f64, u64)>,
+}
+
+impl<T: KeyType> EmaHistogram<T> {
+    /// Panics unless `alpha` lies in `(0, 1]`.
+    pub fn new(alpha: f64) -> Self {
+        assert!(alpha > 0.0 && alpha <= 1.0, "alpha must be in (0, 1]");
+        EmaHistogram {
+            alpha,
+            steps: 0,
+            averages: HashMap::new(),
+        }
+    }
+
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// The number of bumps so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        let current = self.ema(item);
+        self.steps += 1;
+        self.averages.insert(
+            item.clone(),
+            (current * (1.0 - self.alpha) + self.alpha, self.steps),
+        );
+    }
+
+    pub fn ema(&self, item: &T) -> f64 {
+        self.averages
+            .get(item)
+            .map_or(0.0, |(average, step)| self.decayed(*average, *step))
+    }
+
+    pub fn len(&self) -> usize {
+        self.averages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.averages.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, f64)> + '_ {
+        self.averages
+            .iter()
+            .map(|(key, (average, step))| (key, self.decayed(*average, *step)))
+    }
+
+    pub fn ranking(&self) -> Vec<T> {
+        self.ranking_with_emas()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    pub fn ranking_with_emas(&self) -> Vec<(T, f64)> {
+        let mut ranking: Vec<(T, f64)> = self.iter().map(|(k, e)| (k.clone(), e)).collect();
+        ranking.sort_by(|(_, e1), (_, e2)| e2.total_cmp(e1));
+        ranking
+    }
+
+    pub fn mode(&self) -> Option<T> {
+        self.iter()
+            .max_by(|(_, e1), (_, e2)| e1.total_cmp(e2))
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Removes keys whose averages have decayed below `min_ema`.
+    pub fn prune(&mut self, min_ema: f64) {
+        let (alpha, steps) = (self.alpha, self.steps);
+        self.averages.retain(|_, (average, step)| {
+            *average * (1.0 - alpha).powf((steps - *step) as f64) >= min_ema
+        });
+    }
+
+    fn decayed(&self, average: f64, step: u64) -> f64 {
+        average * (1.0 - self.alpha).powf((self.steps - step) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_averages_approach_shares() {
+        let mut h = EmaHistogram::new(0.01);
+        for i in 0..5000 {
+            h.bump(&(i % 4 == 0));
+        }
+        assert!((h.ema(&true) - 0.25).abs() < 0.02);
+        let total: f64 = h.iter().map(|(_, e)| e).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        h.prune(0.5);
+        assert_eq!(h.len(), 1);
+        assert_eq!(h.mode(), Some(false));
+    }
+}
@@ -0,0 +1,173 @@
+//! # Interactive explorer
+//! With the `tui` feature, `explore()` opens a simple line-oriented explorer on the terminal for
+//! inspecting large histograms. It shows one page of entries at a time and reads one command per
+//! line:
+//! * `n` and `p` move to the next and previous pages.
+//! * `s` toggles between sorting by count and by key.
+//! * `b` toggles between plain counts and bars scaled to the largest count shown.
+//! * `/text` shows only keys containing `text`, and `/` alone clears the search.
+//! * `q`, or the end of input, quits.
+//!
+//! `explore_with()` reads commands from any reader and writes pages to any writer, for scripting.
+//!
+//! ```
+//! use hash_histogram::{explore_with, HashHistogram};
+//!
+//! let h: HashHistogram<&str> = ["apple", "banana", "apple", "cherry"].iter().collect();
+//! let mut out = Vec::new();
+//! explore_with(&h, "/an\nq\n".as_bytes(), &mut out, 10).unwrap();
+//! let out = String::from_utf8(out).unwrap();
+//! assert!(out.ends_with("1-1 of 1, by count, matching \"an\"\nbanana: 1\n> "));
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+
+/// Entries shown per page by `explore()`.
+pub const PAGE_SIZE: usize = 20;
+
+/// Width of the bars in the bar view.
+const BAR_WIDTH: usize = 40;
+
+/// Explores `histogram` interactively using standard input and output.
+pub fn explore<T, C>(histogram: &HashHistogram<T, C>) -> io::Result<()>
+where
+    T: KeyType + Ord + Display,
+    C: CounterType + Display,
+{
+    explore_with(
+        histogram,
+        io::stdin().lock(),
+        io::stdout().lock(),
+        PAGE_SIZE,
+    )
+}
+
+/// Explores `histogram`, reading commands from `input` and writing pages of `page_size` entries
+/// to `output`.
+pub fn explore_with<T, C, R, W>(
+    histogram: &HashHistogram<T, C>,
+    input: R,
+    mut output: W,
+    page_size: usize,
+) -> io::Result<()>
+where
+    T: KeyType + Ord + Display,
+    C: CounterType + Display,
+    R: BufRead,
+    W: Write,
+{
+    let page_size = page_size.max(1);
+    let mut entries: Vec<(String, &T, C)> = histogram
+        .iter()
+        .map(|(key, count)| (key.to_string(), key, *count))
+        .collect();
+    let mut by_key = false;
+    let mut bars = false;
+    let mut search = String::new();
+    let mut page = 0;
+    let mut lines = input.lines();
+    loop {
+        if by_key {
+            entries.sort_by_key(|(_, key, _)| *key);
+        } else {
+            entries.sort_by(|(_, k1, c1), (_, k2, c2)| c2.cmp(c1).then_with(|| k1.cmp(k2)));
+        }
+        let matching: Vec<&(String, &T, C)> = entries
+            .iter()
+            .filter(|(text, _, _)| text.contains(&search))
+            .collect();
+        let pages = matching.len().div_ceil(page_size).max(1);
+        page = page.min(pages - 1);
+        let start = (page * page_size).min(matching.len());
+        let shown = &matching[start..(start + page_size).min(matching.len())];
+
+        write!(
+            output,
+            "{}-{} of {}, by {}",
+            (start + 1).min(matching.len()),
+            start + shown.len(),
+            matching.len(),
+            if by_key { "key" } else { "count" }
+        )?;
+        if !search.is_empty() {
+            write!(output, ", matching {:?}", search)?;
+        }
+        writeln!(output)?;
+        let key_width = shown.iter().map(|(t, _, _)| t.chars().count()).max();
+        let max = shown.iter().map(|(_, _, c)| *c).max();
+        for (text, _, count) in shown {
+            if bars {
+                let max = max.unwrap().to_f64().unwrap();
+                let bar = if max > 0.0 {
+                    (count.to_f64().unwrap() / max * BAR_WIDTH as f64).round() as usize
+                } else {
+                    0
+                };
+                let key_width = key_width.unwrap();
+                writeln!(
+                    output,
+                    "{:key_width$} | {} {}",
+                    text,
+                    "#".repeat(bar),
+                    count
+                )?;
+            } else {
+                writeln!(output, "{}: {}", text, count)?;
+            }
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+
+        let Some(command) = lines.next() else {
+            return Ok(());
+        };
+        let command = command?;
+        match command.trim_end() {
+            "q" => return Ok(()),
+            "n" => page = (page + 1).min(pages - 1),
+            "p" => page = page.saturating_sub(1),
+            "s" => by_key = !by_key,
+            "b" => bars = !bars,
+            text if text.starts_with('/') => {
+                search = text[1..].to_string();
+                page = 0;
+            }
+            _ => writeln!(output, "commands: n, p, s, b, /text, q")?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paging_and_views() {
+        let h: HashHistogram<u32> = (0..50).flat_map(|i| vec![i; i as usize % 7]).collect();
+        let mut out = Vec::new();
+        explore_with(&h, "n\nn\nn\nn\ns\nb\n?\n".as_bytes(), &mut out, 20).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let headers: Vec<&str> = out
+            .lines()
+            .filter(|line| line.contains(" of "))
+            .map(|line| line.trim_start_matches("> "))
+            .collect();
+        assert_eq!(
+            headers,
+            vec![
+                "1-20 of 42, by count",
+                "21-40 of 42, by count",
+                "41-42 of 42, by count",
+                "41-42 of 42, by count",
+                "41-42 of 42, by count",
+                "41-42 of 42, by key",
+                "41-42 of 42, by key",
+                "41-42 of 42, by key",
+            ]
+        );
+        assert!(out.contains(&format!("48 | {} 6\n", "#".repeat(BAR_WIDTH))));
+        assert!(out.contains("commands: n, p, s, b, /text, q"));
+    }
+}
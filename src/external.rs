@@ -0,0 +1,249 @@
+//! # External ranking
+//! `ranking_external()` ranks a histogram too large to copy and sort in memory next to the live
+//! map. It sorts the entries in runs of bounded size, spills each run to a file in a temporary
+//! directory, and returns an iterator that merges the runs, holding one entry per run in memory.
+//! Keys are written with the `BinaryKey` encoding used by `to_bytes()`.
+//!
+//! Entries come out in the same order as `ranking_with_counts()`: highest count first, with
+//! ties in the order the histogram iterates its keys. The spill files are removed when the
+//! iterator is dropped.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<u32> = (0..10_000).map(|i| i % 1000).collect();
+//! let ranked = h.ranking_external_with(&std::env::temp_dir(), 100).unwrap();
+//! let ranked: Vec<(u32, usize)> = ranked.collect::<Result<_, _>>().unwrap();
+//! assert_eq!(ranked, h.ranking_with_counts());
+//! ```
+
+use crate::binary::{read_varint, write_varint};
+use crate::{BinaryKey, CounterType, HashHistogram, KeyType};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+/// Entries per run for `ranking_external()`.
+pub const DEFAULT_RUN_LENGTH: usize = 1 << 16;
+
+/// Distinguishes the spill files of concurrent rankings within one process.
+static NEXT_RANKING: AtomicUsize = AtomicUsize::new(0);
+
+impl<T: KeyType + BinaryKey, C: CounterType> HashHistogram<T, C> {
+    pub fn ranking_external(&self, tmp_dir: &Path) -> io::Result<ExternalRanking<T, C>> {
+        self.ranking_external_with(tmp_dir, DEFAULT_RUN_LENGTH)
+    }
+
+    /// Spills sorted runs of at most `run_length` entries to files in `tmp_dir`. The merge keeps
+    /// every run file open, so `run_length` must be large enough that the number of runs,
+    /// `len() / run_length` rounded up, stays within the process's limit on open files. Fails
+    /// rather than overwrite a file that already exists. Panics if `run_length` is zero.
+    pub fn ranking_external_with(
+        &self,
+        tmp_dir: &Path,
+        run_length: usize,
+    ) -> io::Result<ExternalRanking<T, C>> {
+        assert!(run_length > 0, "run length must be positive");
+        let ranking = NEXT_RANKING.fetch_add(1, AtomicOrdering::Relaxed);
+        let mut result = ExternalRanking {
+            runs: Vec::new(),
+            heads: BinaryHeap::new(),
+            error: None,
+        };
+        let mut entries = self.iter().peekable();
+        while entries.peek().is_some() {
+            let mut run: Vec<(&T, &C)> = entries.by_ref().take(run_length).collect();
+            run.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+            let path = tmp_dir.join(format!(
+                "hash_histogram-{}-{}-{}.run",
+                std::process::id(),
+                ranking,
+                result.runs.len()
+            ));
+            let file = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)?;
+            let mut writer = BufWriter::new(file);
+            result.runs.push(Run { path, reader: None });
+            for (key, count) in run {
+                write_entry(&mut writer, key, *count)?;
+            }
+            writer.flush()?;
+        }
+        for index in 0..result.runs.len() {
+            let run = &mut result.runs[index];
+            run.reader = Some(BufReader::new(File::open(&run.path)?));
+            result.advance(index)?;
+        }
+        Ok(result)
+    }
+}
+
+/// An iterator over ranked `(key, count)` entries, merged from runs spilled to disk. Yields an
+/// error, and then stops, if a run cannot be read back.
+#[derive(Debug)]
+pub struct ExternalRanking<T: KeyType, C: CounterType> {
+    runs: Vec<Run>,
+    heads: BinaryHeap<Head<T, C>>,
+    error: Option<io::Error>,
+}
+
+#[derive(Debug)]
+struct Run {
+    path: PathBuf,
+    reader: Option<BufReader<File>>,
+}
+
+/// The next unmerged entry of a run. Higher counts, then earlier runs, come first.
+#[derive(Debug)]
+struct Head<T, C> {
+    count: C,
+    run: Reverse<usize>,
+    key: T,
+}
+
+impl<T, C: Ord> Ord for Head<T, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.count.cmp(&other.count).then(self.run.cmp(&other.run))
+    }
+}
+
+impl<T, C: Ord> PartialOrd for Head<T, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, C: Ord> PartialEq for Head<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T, C: Ord> Eq for Head<T, C> {}
+
+impl<T: KeyType + BinaryKey, C: CounterType> ExternalRanking<T, C> {
+    /// Reads the next entry of run `index` into the heap, if the run has one left.
+    fn advance(&mut self, index: usize) -> io::Result<()> {
+        if let Some(reader) = self.runs[index].reader.as_mut() {
+            match read_entry(reader)? {
+                Some((key, count)) => self.heads.push(Head {
+                    count,
+                    run: Reverse(index),
+                    key,
+                }),
+                None => self.runs[index].reader = None,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: KeyType + BinaryKey, C: CounterType> Iterator for ExternalRanking<T, C> {
+    type Item = io::Result<(T, C)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            self.heads.clear();
+            return Some(Err(error));
+        }
+        let head = self.heads.pop()?;
+        if let Err(error) = self.advance(head.run.0) {
+            self.error = Some(error);
+        }
+        Some(Ok((head.key, head.count)))
+    }
+}
+
+impl<T: KeyType, C: CounterType> Drop for ExternalRanking<T, C> {
+    fn drop(&mut self) {
+        for run in self.runs.drain(..) {
+            drop(run.reader);
+            let _ = fs::remove_file(run.path);
+        }
+    }
+}
+
+/// Writes an entry as a 4-byte little-endian length followed by the encoded key and count.
+fn write_entry<T: BinaryKey, C: CounterType, W: Write>(
+    writer: &mut W,
+    key: &T,
+    count: C,
+) -> io::Result<()> {
+    let mut record = Vec::new();
+    key.encode(&mut record);
+    write_varint(count.to_u128().unwrap(), &mut record);
+    writer.write_all(&(record.len() as u32).to_le_bytes())?;
+    writer.write_all(&record)
+}
+
+fn read_entry<T: BinaryKey, C: CounterType, R: Read>(reader: &mut R) -> io::Result<Option<(T, C)>> {
+    let mut len = [0; 4];
+    match reader.read_exact(&mut len) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        result => result?,
+    }
+    let mut record = vec![0; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut record)?;
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+    let mut input = record.as_slice();
+    let key = T::decode(&mut input).map_err(invalid)?;
+    let count = read_varint(&mut input).map_err(invalid)?;
+    let count =
+        <C as num::NumCast>::from(count).ok_or_else(|| invalid(crate::BinaryError::Overflow))?;
+    Ok(Some((key, count)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_in_memory_ranking() {
+        let words: HashHistogram<String, u64> = (0..5000)
+            .map(|i| format!("w{}", (i * 7919) % 613))
+            .collect();
+        let dir = std::env::temp_dir();
+        let ranked: Vec<(String, u64)> = words
+            .ranking_external_with(&dir, 50)
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(ranked, words.ranking_with_counts());
+
+        let ranking = words.ranking_external_with(&dir, 50).unwrap();
+        let paths: Vec<PathBuf> = ranking.runs.iter().map(|r| r.path.clone()).collect();
+        assert_eq!(paths.len(), 13);
+        assert!(paths.iter().all(|p| p.exists()));
+        drop(ranking);
+        assert!(paths.iter().all(|p| !p.exists()));
+
+        let empty = HashHistogram::<u8>::new();
+        assert_eq!(empty.ranking_external(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_existing_files_are_kept() {
+        let dir =
+            std::env::temp_dir().join(format!("hash_histogram-existing-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let ranking = NEXT_RANKING.load(AtomicOrdering::Relaxed);
+        let occupied: Vec<PathBuf> = (ranking..ranking + 4)
+            .map(|r| dir.join(format!("hash_histogram-{}-{}-0.run", std::process::id(), r)))
+            .collect();
+        for path in occupied.iter() {
+            fs::write(path, b"keep").unwrap();
+        }
+        let h: HashHistogram<u8> = [1, 2, 2].iter().collect();
+        let error = h.ranking_external_with(&dir, 1).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        for path in occupied.iter() {
+            assert_eq!(fs::read(path).unwrap(), b"keep");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
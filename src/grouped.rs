@@ -0,0 +1,198 @@
+//! # Grouped histograms
+//! `GroupedHistogram` keeps a separate `HashHistogram` for each group label, like a pivot table
+//! with groups as rows and keys as columns. `collapse()` sums the counts across all groups.
+//!
+//! ```
+//! use hash_histogram::GroupedHistogram;
+//!
+//! let mut status_by_route = GroupedHistogram::<&str, u16>::new();
+//! for (route, status) in [("/home", 200), ("/home", 200), ("/api", 500), ("/api", 200)] {
+//!     status_by_route.bump(&route, &status);
+//! }
+//!
+//! assert_eq!(status_by_route.group(&"/home").unwrap().count(&200), 2);
+//! assert_eq!(status_by_route.count(&"/api", &500), 1);
+//! assert_eq!(status_by_route.collapse().count(&200), 3);
+//! assert_eq!(status_by_route.len(), 2);
+//! ```
+//!
+//! When groups and keys have the same type, a `GroupedHistogram` can count the transitions of a
+//! Markov chain, with each group holding the counts of the states that followed it.
+//! `from_transitions()` learns these counts from a sequence, and `generate()` samples a new
+//! sequence from them. `rng` must return uniform samples from `[0, 1)`.
+//!
+//! ```
+//! use hash_histogram::GroupedHistogram;
+//!
+//! let chain = GroupedHistogram::<char, char>::from_transitions("abcabcabd".chars());
+//! assert_eq!(chain.count(&'b', &'c'), 2);
+//!
+//! // 'a' is always followed by 'b', which is followed by 'c' twice as often as by 'd'.
+//! let mut draws = [0.1, 0.9, 0.5].into_iter().cycle();
+//! let generated = chain.generate('a', 3, &mut || draws.next().unwrap());
+//! assert_eq!(generated[..2], ['a', 'b']);
+//! assert!(generated[2] == 'c' || generated[2] == 'd');
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GroupedHistogram<G: KeyType, T: KeyType, C: CounterType = usize> {
+    groups: HashMap<G, HashHistogram<T, C>>,
+}
+
+impl<G: KeyType, T: KeyType, C: CounterType> GroupedHistogram<G, T, C> {
+    pub fn new() -> Self {
+        GroupedHistogram::default()
+    }
+
+    pub fn bump(&mut self, group: &G, item: &T) {
+        self.bump_by(group, item, num::one());
+    }
+
+    pub fn bump_by(&mut self, group: &G, item: &T, increment: C) {
+        match self.groups.get_mut(group) {
+            Some(histogram) => histogram.bump_by(item, increment),
+            None => {
+                let mut histogram = HashHistogram::new();
+                histogram.bump_by(item, increment);
+                self.groups.insert(group.clone(), histogram);
+            }
+        }
+    }
+
+    pub fn count(&self, group: &G, item: &T) -> C {
+        self.group(group)
+            .map_or(num::zero(), |histogram| histogram.count(item))
+    }
+
+    pub fn group(&self, group: &G) -> Option<&HashHistogram<T, C>> {
+        self.groups.get(group)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = (&G, &HashHistogram<T, C>)> {
+        self.groups.iter()
+    }
+
+    /// The number of groups.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn total_count(&self) -> C {
+        self.groups.values().map(|h| h.total_count()).sum()
+    }
+
+    /// The total count of each group.
+    pub fn group_totals(&self) -> HashHistogram<G, C> {
+        self.groups()
+            .map(|(group, histogram)| (group.clone(), histogram.total_count()))
+            .collect()
+    }
+
+    /// Sums the counts of each key across all groups.
+    pub fn collapse(&self) -> HashHistogram<T, C> {
+        let mut result = HashHistogram::new();
+        for histogram in self.groups.values() {
+            result.bump_pairs(histogram.iter().map(|(key, count)| (key, *count)));
+        }
+        result
+    }
+}
+
+impl<T: KeyType, C: CounterType> GroupedHistogram<T, T, C> {
+    /// Counts each consecutive pair of items in `sequence` as a transition.
+    pub fn from_transitions<I: IntoIterator<Item = T>>(sequence: I) -> Self {
+        let mut result = GroupedHistogram::new();
+        let mut sequence = sequence.into_iter();
+        if let Some(mut previous) = sequence.next() {
+            for item in sequence {
+                result.bump(&previous, &item);
+                previous = item;
+            }
+        }
+        result
+    }
+
+    /// Samples a sequence of up to `length` items beginning with `start`, choosing each next
+    /// item with probability proportional to its transition count. The sequence ends early at
+    /// an item with no recorded transitions.
+    pub fn generate<R: FnMut() -> f64>(&self, start: T, length: usize, rng: &mut R) -> Vec<T> {
+        let mut result = Vec::with_capacity(length);
+        let mut current = Some(start);
+        while let Some(item) = current {
+            if result.len() == length {
+                break;
+            }
+            current = self.group(&item).and_then(|next| sample_key(next, rng));
+            result.push(item);
+        }
+        result
+    }
+}
+
+/// Picks a key with probability proportional to its count.
+fn sample_key<T: KeyType, C: CounterType, R: FnMut() -> f64>(
+    histogram: &HashHistogram<T, C>,
+    rng: &mut R,
+) -> Option<T> {
+    let mut remaining = rng() * histogram.total_count().to_f64().unwrap();
+    let mut last = None;
+    for (key, count) in histogram.iter() {
+        if *count == num::zero() {
+            continue;
+        }
+        remaining -= count.to_f64().unwrap();
+        if remaining < 0.0 {
+            return Some(key.clone());
+        }
+        last = Some(key);
+    }
+    last.cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_matches_totals() {
+        let mut h = GroupedHistogram::<u8, char, u32>::new();
+        h.bump(&1, &'a');
+        h.bump_by(&1, &'b', 4);
+        h.bump_by(&2, &'a', 2);
+        assert_eq!(h.count(&3, &'a'), 0);
+        assert_eq!(h.group_totals().count(&1), 5);
+        let collapsed = h.collapse();
+        assert_eq!(collapsed.count(&'a'), 3);
+        assert_eq!(collapsed.total_count(), h.total_count());
+    }
+
+    #[test]
+    fn test_generate_follows_transitions() {
+        let chain = GroupedHistogram::<u8, u8>::from_transitions([0, 1, 2, 0, 1, 3, 0, 1, 2]);
+        let mut rng = crate::stats::test_rng(7);
+        let generated = chain.generate(0, 1000, &mut rng);
+        assert_eq!(generated.len(), 1000);
+        for pair in generated.windows(2) {
+            assert!(chain.count(&pair[0], &pair[1]) > 0);
+        }
+        let after_one: HashHistogram<u8> = generated
+            .windows(2)
+            .filter(|pair| pair[0] == 1)
+            .map(|pair| pair[1])
+            .collect();
+        assert!(after_one.count(&2) > after_one.count(&3));
+        assert_eq!(chain.generate(3, 3, &mut rng), vec![3, 0, 1]);
+        assert_eq!(chain.generate(9, 5, &mut rng), vec![9]);
+        assert!(chain.generate(0, 0, &mut rng).is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! # Heavy hitters
+//! `HeavyHitters` finds the most frequent keys of an unbounded stream while keeping at most a
+//! fixed number of counters, using the Space-Saving algorithm of Metwally, Agrawal, and
+//! El Abbadi. Every tracked key carries an error bound: its true count lies between
+//! `count() - error()` and `count()`. Any key whose true count exceeds
+//! `total_count() / capacity()` is guaranteed to be tracked.
+//!
+//! ```
+//! use hash_histogram::HeavyHitters;
+//!
+//! let mut h = HeavyHitters::<&str>::new(3);
+//! for s in ["a", "b", "a", "c", "a", "d", "a", "b", "e", "a"].iter() {
+//!     h.bump(s);
+//! }
+//! assert_eq!(h.len(), 3);
+//! assert_eq!(h.mode(), Some("a"));
+//! assert!(h.guaranteed_count(&"a") <= 5 && 5 <= h.count(&"a"));
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HeavyHitters<T: KeyType, C: CounterType = usize> {
+    capacity: usize,
+    counters: HashMap<T, (C, C)>,
+    total: C,
+}
+
+impl<T: KeyType, C: CounterType> HeavyHitters<T, C> {
+    /// Creates a tracker holding at most `capacity` counters. Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        HeavyHitters {
+            capacity,
+            counters: HashMap::with_capacity(capacity),
+            total: C::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, num::one());
+    }
+
+    /// When all counters are in use, an untracked key replaces the key with the smallest count,
+    /// inheriting that count as its error bound.
+    pub fn bump_by(&mut self, item: &T, increment: C) {
+        self.total += increment;
+        if let Some((count, _)) = self.counters.get_mut(item) {
+            *count += increment;
+        } else if self.counters.len() < self.capacity {
+            self.counters
+                .insert(item.clone(), (increment, C::default()));
+        } else {
+            let (evicted, min) = self
+                .counters
+                .iter()
+                .min_by_key(|(_, (count, _))| *count)
+                .map(|(key, (count, _))| (key.clone(), *count))
+                .unwrap();
+            self.counters.remove(&evicted);
+            self.counters.insert(item.clone(), (min + increment, min));
+        }
+    }
+
+    /// Upper bound on the number of times `item` was bumped. Untracked keys report zero, although
+    /// their true counts may be as large as the smallest tracked count.
+    pub fn count(&self, item: &T) -> C {
+        self.counters
+            .get(item)
+            .map_or(C::default(), |(count, _)| *count)
+    }
+
+    /// Maximum overestimate in `count(item)`, or `None` if `item` is not tracked.
+    pub fn error(&self, item: &T) -> Option<C> {
+        self.counters.get(item).map(|(_, error)| *error)
+    }
+
+    /// Lower bound on the number of times `item` was bumped.
+    pub fn guaranteed_count(&self, item: &T) -> C {
+        self.counters
+            .get(item)
+            .map_or(C::default(), |(count, error)| *count - *error)
+    }
+
+    pub fn total_count(&self) -> C {
+        self.total
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, C)> + '_ {
+        self.counters.iter().map(|(key, (count, _))| (key, *count))
+    }
+
+    pub fn ranking(&self) -> Vec<T> {
+        self.ranking_with_counts()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    pub fn ranking_with_counts(&self) -> Vec<(T, C)> {
+        self.to_histogram().ranking_with_counts()
+    }
+
+    pub fn mode(&self) -> Option<T> {
+        self.to_histogram().mode()
+    }
+
+    /// The estimated counts of all tracked keys.
+    pub fn to_histogram(&self) -> HashHistogram<T, C> {
+        let mut result = HashHistogram::new();
+        for (key, count) in self.iter() {
+            result.bump_by(key, count);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space_saving() {
+        let mut h = HeavyHitters::<usize>::new(4);
+        for i in 0..1000 {
+            h.bump(&(i % 3));
+            if i % 4 == 0 {
+                h.bump(&(1000 + i));
+            }
+        }
+        assert_eq!(h.len(), 4);
+        assert_eq!(h.total_count(), 1250);
+        for key in 0..3 {
+            let actual = (1002 - key) / 3;
+            assert!(h.guaranteed_count(&key) <= actual);
+            assert!(h.count(&key) >= actual);
+        }
+        assert_eq!(h.count(&5), 0);
+        assert_eq!(h.error(&5), None);
+
+        let mut top = h.ranking();
+        top.truncate(3);
+        top.sort();
+        assert_eq!(top, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_exact_below_capacity() {
+        let mut h = HeavyHitters::<&str, u32>::new(10);
+        h.bump_by(&"x", 5);
+        h.bump(&"y");
+        assert_eq!(h.error(&"x"), Some(0));
+        assert_eq!(h.guaranteed_count(&"x"), 5);
+        assert_eq!(h.ranking_with_counts(), vec![("x", 5), ("y", 1)]);
+    }
+}
@@ -0,0 +1,233 @@
+//! # Hybrid histograms
+//! `HybridHistogram` keeps exact counts for at most a fixed number of frequent keys and
+//! approximates the rest with a `CountMinSketch`, for high-cardinality streams where a
+//! `HashHistogram` would not fit in memory but the most frequent keys matter most.
+//!
+//! Keys are counted exactly until the head is full. After that, new keys are counted in the
+//! sketch, and the threshold for the head adapts to the smallest count in it: once a key's
+//! estimate exceeds that count, the key moves into the head, starting from its estimate, and the
+//! smallest head key moves to the sketch.
+//!
+//! `count()` never underestimates. With probability at least `1 - delta`, each count exceeds its
+//! true value by at most `error_bound()`, which is `epsilon` times the total count held in the
+//! sketch. Keys that have been in the head since they were first bumped are counted exactly.
+//!
+//! The head is indexed by count, so a bump takes time logarithmic in the capacity, whether it
+//! updates the head or the sketch.
+//!
+//! ```
+//! use hash_histogram::HybridHistogram;
+//!
+//! let mut h = HybridHistogram::<u32>::new(10, 0.01, 0.01);
+//! for i in 0..10_000u32 {
+//!     h.bump(&(i % 7));
+//!     h.bump(&(1000 + i));
+//! }
+//! assert!(h.count(&3) >= 1429);
+//! assert!(h.count(&3) as f64 <= 1429.0 + h.error_bound());
+//! assert!(h.count(&1500) >= 1);
+//! assert_eq!(h.total_count(), 20_000);
+//! ```
+
+use crate::{CountMinSketch, CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "HybridFields<T, C>"))]
+pub struct HybridHistogram<T: KeyType, C: CounterType = usize> {
+    capacity: usize,
+    /// The count of each head key, and its estimate when it moved into the head.
+    head: HashMap<T, (C, C)>,
+    /// The head keys grouped by count, for finding the smallest.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    by_count: BTreeMap<C, HashSet<T>>,
+    tail: CountMinSketch<T, C>,
+    total: C,
+}
+
+/// The serialized form of `HybridHistogram`, from which the index of the head is rebuilt.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct HybridFields<T: KeyType, C: CounterType> {
+    capacity: usize,
+    head: HashMap<T, (C, C)>,
+    tail: CountMinSketch<T, C>,
+    total: C,
+}
+
+#[cfg(feature = "serde")]
+impl<T: KeyType, C: CounterType> From<HybridFields<T, C>> for HybridHistogram<T, C> {
+    fn from(fields: HybridFields<T, C>) -> Self {
+        let mut by_count: BTreeMap<C, HashSet<T>> = BTreeMap::new();
+        for (key, (count, _)) in fields.head.iter() {
+            by_count.entry(*count).or_default().insert(key.clone());
+        }
+        HybridHistogram {
+            capacity: fields.capacity,
+            head: fields.head,
+            by_count,
+            tail: fields.tail,
+            total: fields.total,
+        }
+    }
+}
+
+impl<T: KeyType, C: CounterType> HybridHistogram<T, C> {
+    /// Creates a histogram counting at most `capacity` keys exactly, with a sketch sized as by
+    /// `CountMinSketch::with_error(epsilon, delta)`. Panics if `capacity` is zero, or if
+    /// `epsilon` or `delta` is invalid.
+    pub fn new(capacity: usize, epsilon: f64, delta: f64) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        HybridHistogram {
+            capacity,
+            head: HashMap::with_capacity(capacity),
+            by_count: BTreeMap::new(),
+            tail: CountMinSketch::with_error(epsilon, delta),
+            total: C::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, num::one());
+    }
+
+    pub fn bump_by(&mut self, item: &T, increment: C) {
+        self.total += increment;
+        if let Some((count, _)) = self.head.get_mut(item) {
+            let previous = *count;
+            *count += increment;
+            let updated = *count;
+            let key = self.unindex(item, previous);
+            self.by_count.entry(updated).or_default().insert(key);
+        } else if self.head.len() < self.capacity {
+            // The head never shrinks, so the sketch is still empty.
+            self.head.insert(item.clone(), (increment, C::default()));
+            self.by_count
+                .entry(increment)
+                .or_default()
+                .insert(item.clone());
+        } else {
+            self.tail.bump_by(item, increment);
+            let estimate = self.tail.count(item);
+            let (&count, smallest) = self.by_count.first_key_value().unwrap();
+            if estimate > count {
+                let smallest = smallest.iter().next().unwrap().clone();
+                let smallest = self.unindex(&smallest, count);
+                let (_, start) = self.head.remove(&smallest).unwrap();
+                // The sketch already holds the count from before `smallest` entered the head.
+                self.tail.bump_by(&smallest, count - start);
+                self.head.insert(item.clone(), (estimate, estimate));
+                self.by_count
+                    .entry(estimate)
+                    .or_default()
+                    .insert(item.clone());
+            }
+        }
+    }
+
+    /// Removes `item` from the index of head keys with `count`, returning the indexed key.
+    fn unindex(&mut self, item: &T, count: C) -> T {
+        let keys = self.by_count.get_mut(&count).unwrap();
+        let key = keys.take(item).unwrap();
+        if keys.is_empty() {
+            self.by_count.remove(&count);
+        }
+        key
+    }
+
+    /// An estimate of the count of `item` that is never less than its true count.
+    pub fn count(&self, item: &T) -> C {
+        self.head
+            .get(item)
+            .map_or_else(|| self.tail.count(item), |(count, _)| *count)
+    }
+
+    /// Whether `count(item)` is exact because `item` has been in the head since it was first
+    /// bumped.
+    pub fn is_exact(&self, item: &T) -> bool {
+        self.head
+            .get(item)
+            .is_some_and(|(_, start)| *start == C::default())
+    }
+
+    /// The largest overestimate in `count()`, with probability at least `1 - delta`.
+    pub fn error_bound(&self) -> f64 {
+        std::f64::consts::E / self.tail.width() as f64 * self.tail.total_count().to_f64().unwrap()
+    }
+
+    pub fn total_count(&self) -> C {
+        self.total
+    }
+
+    /// The counts of the keys in the head.
+    pub fn head(&self) -> HashHistogram<T, C> {
+        self.head
+            .iter()
+            .map(|(key, (count, _))| (key.clone(), *count))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_never_underestimates() {
+        let mut h = HybridHistogram::<u32>::new(5, 0.05, 0.01);
+        let mut exact = HashHistogram::<u32>::new();
+        for i in 0..5000u32 {
+            let key = if i % 2 == 0 { i % 10 + 100 } else { i };
+            h.bump(&key);
+            exact.bump(&key);
+        }
+        for (key, count) in exact.iter() {
+            assert!(h.count(key) >= *count);
+            assert!(h.count(key) as f64 <= *count as f64 + h.error_bound());
+        }
+        let head = h.head();
+        assert_eq!(head.len(), 5);
+        assert!(head.keys().all(|key| (100..110).contains(key)));
+        assert_eq!(h.total_count(), 5000);
+
+        let mut small = HybridHistogram::<char>::new(3, 0.1, 0.1);
+        "abcab".chars().for_each(|c| small.bump(&c));
+        assert!(small.is_exact(&'a'));
+        assert_eq!(small.count(&'b'), 2);
+        assert_eq!(small.error_bound(), 0.0);
+    }
+
+    #[test]
+    fn test_index_matches_head() {
+        let mut h = HybridHistogram::<u16, u32>::new(8, 0.01, 0.01);
+        let mut rng = crate::stats::test_rng(9);
+        for _ in 0..3000 {
+            let key = (rng() * rng() * 200.0) as u16;
+            h.bump_by(&key, 1 + (rng() * 3.0) as u32);
+        }
+        let mut indexed: Vec<(u16, u32)> = h
+            .by_count
+            .iter()
+            .flat_map(|(count, keys)| keys.iter().map(move |key| (*key, *count)))
+            .collect();
+        indexed.sort();
+        let mut head: Vec<(u16, u32)> = h.head().iter().map(|(k, c)| (*k, *c)).collect();
+        head.sort();
+        assert_eq!(indexed, head);
+        assert_eq!(head.len(), 8);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&h).unwrap();
+            let restored: HybridHistogram<u16, u32> = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored, h);
+        }
+    }
+}
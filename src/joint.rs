@@ -0,0 +1,127 @@
+//! # Joint histograms
+//! `JointHistogram` counts pairs of values, and projects those counts onto either value. The
+//! marginal histograms count each value regardless of its partner, and the conditional
+//! histograms count the partners of one fixed value.
+//!
+//! ```
+//! use hash_histogram::JointHistogram;
+//!
+//! let mut weather = JointHistogram::<&str, &str>::new();
+//! for (sky, umbrella) in [("rain", "yes"), ("rain", "yes"), ("sun", "no"), ("rain", "no")] {
+//!     weather.bump(&sky, &umbrella);
+//! }
+//!
+//! assert_eq!(weather.count(&"rain", &"yes"), 2);
+//! assert_eq!(weather.marginal_a().count(&"rain"), 3);
+//! assert_eq!(weather.marginal_b().count(&"no"), 2);
+//! assert_eq!(
+//!     weather.conditional_given_a(&"rain").ranking_with_counts(),
+//!     vec![("yes", 2), ("no", 1)]
+//! );
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JointHistogram<A: KeyType, B: KeyType, C: CounterType = usize> {
+    joint: HashHistogram<(A, B), C>,
+}
+
+impl<A: KeyType, B: KeyType, C: CounterType> JointHistogram<A, B, C> {
+    pub fn new() -> Self {
+        JointHistogram::default()
+    }
+
+    pub fn bump(&mut self, a: &A, b: &B) {
+        self.bump_by(a, b, num::one());
+    }
+
+    pub fn bump_by(&mut self, a: &A, b: &B, increment: C) {
+        self.joint.bump_by(&(a.clone(), b.clone()), increment);
+    }
+
+    pub fn count(&self, a: &A, b: &B) -> C {
+        self.joint.count(&(a.clone(), b.clone()))
+    }
+
+    /// The number of distinct pairs.
+    pub fn len(&self) -> usize {
+        self.joint.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.joint.is_empty()
+    }
+
+    pub fn total_count(&self) -> C {
+        self.joint.total_count()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&A, &B, C)> {
+        self.joint.iter().map(|((a, b), count)| (a, b, *count))
+    }
+
+    /// The underlying histogram of pairs.
+    pub fn joint(&self) -> &HashHistogram<(A, B), C> {
+        &self.joint
+    }
+
+    pub fn marginal_a(&self) -> HashHistogram<A, C> {
+        self.iter()
+            .map(|(a, _, count)| (a.clone(), count))
+            .collect()
+    }
+
+    pub fn marginal_b(&self) -> HashHistogram<B, C> {
+        self.iter()
+            .map(|(_, b, count)| (b.clone(), count))
+            .collect()
+    }
+
+    /// Counts of each `B` paired with `a`. Divide by `marginal_a().count(a)` for conditional
+    /// probabilities.
+    pub fn conditional_given_a(&self, a: &A) -> HashHistogram<B, C> {
+        self.iter()
+            .filter(|(key, _, _)| *key == a)
+            .map(|(_, b, count)| (b.clone(), count))
+            .collect()
+    }
+
+    /// Counts of each `A` paired with `b`.
+    pub fn conditional_given_b(&self, b: &B) -> HashHistogram<A, C> {
+        self.iter()
+            .filter(|(_, key, _)| *key == b)
+            .map(|(a, _, count)| (a.clone(), count))
+            .collect()
+    }
+}
+
+impl<A: KeyType, B: KeyType, C: CounterType> From<HashHistogram<(A, B), C>>
+    for JointHistogram<A, B, C>
+{
+    fn from(joint: HashHistogram<(A, B), C>) -> Self {
+        JointHistogram { joint }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marginals_sum_to_total() {
+        let pairs: HashHistogram<(u8, char), u32> =
+            [(1, 'x'), (1, 'y'), (2, 'x'), (2, 'x'), (3, 'z')]
+                .into_iter()
+                .collect();
+        let h = JointHistogram::from(pairs);
+        assert_eq!(h.len(), 4);
+        assert_eq!(h.marginal_a().total_count(), h.total_count());
+        assert_eq!(h.marginal_b().count(&'x'), 3);
+        assert_eq!(h.conditional_given_b(&'x').count(&2), 2);
+        assert_eq!(h.conditional_given_a(&4).len(), 0);
+    }
+}
@@ -0,0 +1,128 @@
+//! # Kernel density estimates
+//! Histograms with numeric keys, as well as `BinnedHistogram`, can produce Gaussian kernel density
+//! estimates for plotting smooth distribution curves. Each key (or bin midpoint) contributes a
+//! kernel weighted by its count (or bin weight). The estimate is evaluated at evenly spaced points
+//! extending three bandwidths beyond the smallest and largest keys.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<u32> = [1, 2, 2, 3, 3, 3, 10].iter().collect();
+//! let curve = h.kde(0.5);
+//! assert_eq!(curve.len(), 100);
+//! let (peak, _) = curve
+//!     .iter()
+//!     .copied()
+//!     .max_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+//!     .unwrap();
+//! assert!((peak - 2.7).abs() < 0.5);
+//! ```
+
+use crate::{BinnedHistogram, CounterType, HashHistogram, KeyType};
+use num::ToPrimitive;
+use std::f64::consts::PI;
+
+const DEFAULT_POINTS: usize = 100;
+
+impl<T: KeyType + ToPrimitive, C: CounterType> HashHistogram<T, C> {
+    pub fn kde(&self, bandwidth: f64) -> Vec<(f64, f64)> {
+        self.kde_with_points(bandwidth, DEFAULT_POINTS)
+    }
+
+    /// Evaluates the estimate at `points` locations. Keys that cannot be represented as `f64`
+    /// are ignored. Panics unless `bandwidth` is positive and finite.
+    pub fn kde_with_points(&self, bandwidth: f64, points: usize) -> Vec<(f64, f64)> {
+        let samples = self
+            .iter()
+            .filter_map(|(key, count)| Some((key.to_f64()?, count.to_f64()?)))
+            .collect::<Vec<_>>();
+        gaussian_kde(&samples, bandwidth, points)
+    }
+}
+
+impl BinnedHistogram {
+    pub fn kde(&self, bandwidth: f64) -> Vec<(f64, f64)> {
+        self.kde_with_points(bandwidth, DEFAULT_POINTS)
+    }
+
+    /// Evaluates the estimate at `points` locations, treating the weight of each bin as
+    /// concentrated at its midpoint. Underflow and overflow are ignored. Panics unless
+    /// `bandwidth` is positive and finite.
+    pub fn kde_with_points(&self, bandwidth: f64, points: usize) -> Vec<(f64, f64)> {
+        let samples = self
+            .bins()
+            .map(|(range, stats)| ((range.start + range.end) / 2.0, stats.weight()))
+            .collect::<Vec<_>>();
+        gaussian_kde(&samples, bandwidth, points)
+    }
+}
+
+fn gaussian_kde(samples: &[(f64, f64)], bandwidth: f64, points: usize) -> Vec<(f64, f64)> {
+    assert!(
+        bandwidth > 0.0 && bandwidth.is_finite(),
+        "bandwidth must be positive and finite"
+    );
+    let total_weight: f64 = samples.iter().map(|(_, w)| w).sum();
+    if points == 0 || total_weight <= 0.0 {
+        return vec![];
+    }
+    let low = samples
+        .iter()
+        .map(|(x, _)| *x)
+        .fold(f64::INFINITY, f64::min)
+        - 3.0 * bandwidth;
+    let high = samples
+        .iter()
+        .map(|(x, _)| *x)
+        .fold(f64::NEG_INFINITY, f64::max)
+        + 3.0 * bandwidth;
+    let step = if points > 1 {
+        (high - low) / (points - 1) as f64
+    } else {
+        0.0
+    };
+    let scale = 1.0 / (total_weight * bandwidth * (2.0 * PI).sqrt());
+    (0..points)
+        .map(|i| {
+            let x = low + step * i as f64;
+            let density = samples
+                .iter()
+                .map(|(center, weight)| {
+                    let z = (x - center) / bandwidth;
+                    weight * (-0.5 * z * z).exp()
+                })
+                .sum::<f64>();
+            (x, density * scale)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_density_integrates_to_one() {
+        let h: HashHistogram<i64> = [-3, 0, 0, 1, 4, 4, 4].iter().collect();
+        let curve = h.kde_with_points(1.0, 1000);
+        let step = curve[1].0 - curve[0].0;
+        let area: f64 = curve.iter().map(|(_, d)| d * step).sum();
+        assert!((area - 1.0).abs() < 0.01);
+        assert_eq!(curve[0].0, -6.0);
+        assert_eq!(curve[999].0, 7.0);
+        assert!(HashHistogram::<u8>::new().kde(1.0).is_empty());
+    }
+
+    #[test]
+    fn test_binned_kde() {
+        let mut b = BinnedHistogram::uniform(0.0, 4.0, 4);
+        b.record_weighted(0.5, 3.0);
+        b.record(3.5);
+        let curve = b.kde_with_points(0.5, 13);
+        assert_eq!(curve.len(), 13);
+        assert_eq!(curve[0].0, -1.0);
+        let at_first_bin = curve.iter().find(|(x, _)| *x == 0.5).unwrap().1;
+        let at_last_bin = curve.iter().find(|(x, _)| *x == 3.5).unwrap().1;
+        assert!((at_first_bin / at_last_bin - 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,160 @@
+//! # Label tables
+//! A `LabelTable` assigns a compact integer ID to each key, so that downstream systems such as
+//! feature stores can refer to keys by number. `label_table()` numbers the keys of a histogram in
+//! sorted order. Extending a table with further histograms keeps existing IDs unchanged and
+//! numbers new keys after them.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<&str> = ["cat", "ant", "cat", "bee"].iter().collect();
+//! let mut table = h.label_table();
+//! assert_eq!(table.id(&"ant"), Some(0));
+//! assert_eq!(table.label(2), Some(&"cat"));
+//! assert_eq!(table.encode(&h), vec![(0, 1), (1, 1), (2, 2)]);
+//!
+//! let later: HashHistogram<&str> = ["ant", "dog"].iter().collect();
+//! table.extend_from(&later);
+//! assert_eq!(table.id(&"ant"), Some(0));
+//! assert_eq!(table.id(&"dog"), Some(3));
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Serializes as the list of labels in ID order.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        from = "Vec<T>",
+        into = "Vec<T>",
+        bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>")
+    )
+)]
+pub struct LabelTable<T: KeyType> {
+    labels: Vec<T>,
+    ids: HashMap<T, usize>,
+}
+
+impl<T: KeyType> LabelTable<T> {
+    pub fn new() -> Self {
+        LabelTable::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    pub fn id(&self, label: &T) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    pub fn label(&self, id: usize) -> Option<&T> {
+        self.labels.get(id)
+    }
+
+    /// Returns the ID of `label`, assigning the next unused ID if it is new.
+    pub fn insert(&mut self, label: &T) -> usize {
+        match self.ids.get(label) {
+            Some(id) => *id,
+            None => {
+                let id = self.labels.len();
+                self.labels.push(label.clone());
+                self.ids.insert(label.clone(), id);
+                id
+            }
+        }
+    }
+
+    /// Assigns IDs to any keys of `histogram` not already in the table, in sorted order.
+    pub fn extend_from<C: CounterType>(&mut self, histogram: &HashHistogram<T, C>)
+    where
+        T: Ord,
+    {
+        let mut new_labels: Vec<&T> = histogram
+            .iter()
+            .map(|(key, _)| key)
+            .filter(|key| !self.ids.contains_key(key))
+            .collect();
+        new_labels.sort();
+        for label in new_labels {
+            self.insert(label);
+        }
+    }
+
+    /// `(id, label)` pairs in ID order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.labels.iter().enumerate()
+    }
+
+    /// The counts of `histogram` keyed by ID, in ID order. Panics if a key is not in the table.
+    pub fn encode<C: CounterType>(&self, histogram: &HashHistogram<T, C>) -> Vec<(usize, C)> {
+        let mut encoded: Vec<(usize, C)> = histogram
+            .iter()
+            .map(|(key, count)| {
+                let id = self.id(key).expect("key missing from label table");
+                (id, *count)
+            })
+            .collect();
+        encoded.sort_by_key(|(id, _)| *id);
+        encoded
+    }
+}
+
+impl<T: KeyType> From<Vec<T>> for LabelTable<T> {
+    fn from(labels: Vec<T>) -> Self {
+        let mut table = LabelTable::new();
+        for label in labels.iter() {
+            table.insert(label);
+        }
+        table
+    }
+}
+
+impl<T: KeyType> From<LabelTable<T>> for Vec<T> {
+    fn from(table: LabelTable<T>) -> Self {
+        table.labels
+    }
+}
+
+impl<T: KeyType + Ord, C: CounterType> HashHistogram<T, C> {
+    /// Numbers the keys of this histogram in sorted order.
+    pub fn label_table(&self) -> LabelTable<T> {
+        let mut table = LabelTable::new();
+        table.extend_from(self);
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_table_round_trip() {
+        let h: HashHistogram<String> = ["x", "y", "x"].iter().map(|s| s.to_string()).collect();
+        let mut table = h.label_table();
+        table.insert(&"a".to_string());
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.id(&"a".to_string()), Some(2));
+        #[cfg(feature = "serde")]
+        {
+            let serialized = serde_json::to_string(&table).unwrap();
+            assert_eq!(serialized, r#"["x","y","a"]"#);
+            let deserialized: LabelTable<String> = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized, table);
+        }
+        assert_eq!(
+            table.iter().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+    }
+}
@@ -69,8 +69,34 @@
 //! assert_eq!(mode_values(nums.iter().map(|n| n + 1)).unwrap(), 201);
 //! ```
 //!
+//! The `HistogramIterator` extension trait offers the same conveniences as iterator methods:
+//!
+//! ```
+//! use hash_histogram::HistogramIterator;
+//! let words = vec!["to", "be", "or", "not", "to", "be", "to"];
+//!
+//! assert_eq!(words.iter().mode(), Some("to"));
+//! assert_eq!(words.iter().map(|w| w.len()).mode(), Some(2));
+//! assert_eq!(words.iter().histogram().count(&"be"), 2);
+//! ```
+//!
+//! When the keys are numbers, the counter type of a `HashHistogram` often cannot be inferred.
+//! The `Histogram` alias, `HashHistogram::counted()`, and `histogram_of()` all fix it to `usize`:
+//!
+//! ```
+//! use hash_histogram::{histogram_of, HashHistogram, Histogram};
+//!
+//! let lengths = HashHistogram::counted([3, 5, 3]);
+//! assert_eq!(lengths.count(&3), 2);
+//! let lengths = histogram_of(["a", "bb", "cc"].iter().map(|s| s.len()));
+//! assert_eq!(lengths.mode(), Some(2));
+//! let mut empty = Histogram::new();
+//! empty.bump(&7);
+//! ```
+//!
 //! `HashHistogram` supports common Rust data structure operations. It implements the
-//! `FromIterator` and `Extend` traits, and derives `serde`:
+//! `FromIterator` and `Extend` traits, and derives `serde` when the default `serde` feature is
+//! enabled:
 //! ```
 //! use hash_histogram::HashHistogram;
 //!
@@ -80,12 +106,19 @@
 //! // Extension from an iterator
 //! h.extend([200, 400, 200, 500, 200].iter());
 //!
+//! // Initialization and extension from pre-counted (key, count) pairs
+//! let mut pairs: HashHistogram<&str> = [("x", 3), ("y", 2)].into_iter().collect();
+//! pairs.extend([("x", 1), ("z", 4)]);
+//! assert_eq!(pairs.count(&"x"), 4);
+//!
+//! # #[cfg(feature = "serde")] {
 //! // Serialization
 //! let serialized = serde_json::to_string(&h).unwrap();
 //!
 //! // Deserialization
 //! let deserialized: HashHistogram<isize> = serde_json::from_str(&serialized).unwrap();
 //! assert_eq!(deserialized, h);
+//! # }
 //! ```
 //!
 
@@ -104,24 +137,187 @@
 //    limitations under the License.
 
 use core::fmt;
-use num::Unsigned;
+use num::traits::SaturatingAdd;
+use num::{Bounded, CheckedAdd, NumCast, Signed, Unsigned};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::collections::hash_map::Iter;
-use std::collections::{HashMap, HashSet};
+use std::borrow::Borrow;
+use std::cmp::Reverse;
+use std::collections::hash_map::{Drain, Iter};
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::iter::Sum;
-use std::ops::AddAssign;
+use std::ops::{AddAssign, Mul};
 use trait_set::trait_set;
 
+pub mod alias;
+pub mod align;
+#[cfg(feature = "test-util")]
+pub mod assertions;
+pub mod bandit;
+pub mod bayes;
+pub mod binary;
+pub mod binned;
+pub mod cardinality;
+pub mod cluster;
+pub mod concurrent;
+pub mod convert;
+pub mod count_min;
+pub mod cover;
+pub mod csv;
+pub mod decaying;
+pub mod ema;
+#[cfg(feature = "tui")]
+pub mod explore;
+pub mod external;
+pub mod grouped;
+pub mod heavy_hitters;
+pub mod hybrid;
+pub mod joint;
+pub mod kde;
+pub mod labels;
+pub mod log_histogram;
+pub mod long_tail;
+pub mod merge;
+pub mod mode_tracking;
+pub mod ngrams;
+pub mod parallel;
+#[cfg(feature = "persistent")]
+pub mod persistent;
+pub mod privacy;
+pub mod query;
+pub mod quota;
+pub mod rate_limit;
+pub mod render;
+pub mod sampler;
+pub mod shared;
+pub mod signed;
+pub mod simulate;
+pub mod sliding;
+pub mod smoothing;
+#[cfg(feature = "serde")]
+pub mod sorted_serde;
+mod stats;
+pub mod text;
+pub mod tf_idf;
+pub mod weighted;
+
+pub use alias::AliasCycle;
+pub use align::{align, AlignedCounts};
+pub use bayes::PosteriorSummary;
+pub use binary::{BinaryError, BinaryKey};
+pub use binned::{BinComparison, BinRule, BinStats, BinnedHistogram};
+pub use cardinality::ApproxLabelCounter;
+pub use cluster::{cluster_histograms, Clustering, Metric};
+pub use concurrent::ConcurrentHashHistogram;
+pub use convert::OverflowError;
+pub use count_min::CountMinSketch;
+pub use cover::min_keys_covering;
+pub use csv::{export_wide_csv, CsvError, CsvOptions};
+pub use decaying::DecayingHistogram;
+pub use ema::EmaHistogram;
+#[cfg(feature = "tui")]
+pub use explore::{explore, explore_with};
+pub use external::ExternalRanking;
+pub use grouped::GroupedHistogram;
+pub use heavy_hitters::HeavyHitters;
+pub use hybrid::HybridHistogram;
+pub use joint::JointHistogram;
+pub use labels::LabelTable;
+pub use log_histogram::LogHistogram;
+pub use long_tail::HeadTailSplit;
+pub use merge::{merge_all, merge_all_widened, MergeError, MergedHistograms};
+pub use mode_tracking::ModeTrackingHistogram;
+#[cfg(feature = "persistent")]
+pub use persistent::PersistentHistogram;
+#[cfg(feature = "serde")]
+pub use privacy::serialize_redacted;
+pub use privacy::{HashedHistogram, SuppressionReport};
+pub use query::{Comparison, KeyTest, Query, QueryError};
+pub use quota::QuotaExceeded;
+pub use rate_limit::{Decision, RateLimiterHistogram};
+pub use render::{DisplayOptions, DisplayOrder, DisplayWith, NumberFormat};
+pub use sampler::AliasSampler;
+pub use shared::SharedHistogram;
+pub use signed::SignedHistogram;
+pub use simulate::{simulate_ops, ApplyOp, HistogramOp};
+pub use sliding::{SlidingWindowHistogram, Window};
+#[cfg(feature = "serde")]
+pub use sorted_serde::{deserialize_entries, serialize_sorted_by_count, serialize_sorted_by_key};
+pub use tf_idf::{document_frequencies, tf_idf};
+pub use weighted::{merge_weighted, WeightedHistogram};
+
 trait_set! {
-    pub trait KeyType = Debug + Hash + Clone + Eq + Default;
-    pub trait CounterType = Copy + Clone + Unsigned + AddAssign + Ord + Sum + Default;
+    pub trait KeyType = Hash + Clone + Eq + Default;
+    pub trait CounterType =
+        Copy + Clone + Unsigned + Bounded + NumCast + CheckedAdd + AddAssign + Ord + Sum + Default;
+    pub trait SignedCounterType = Copy + Clone + Signed + NumCast + AddAssign + Ord + Sum + Default;
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+/// Determines how `rank_of_with()` assigns ranks to keys with equal counts. Ranks start at zero,
+/// matching positions in `ranking()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum RankTies {
+    /// Tied keys share the best rank, and the ranks they occupy are skipped (0, 1, 1, 3).
+    #[default]
+    Competition,
+    /// Tied keys share the best rank, and no ranks are skipped (0, 1, 1, 2).
+    Dense,
+    /// Tied keys share the worst rank (0, 2, 2, 3).
+    Modified,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "HistogramFields<T, C>"))]
 pub struct HashHistogram<T: KeyType, C: CounterType = usize> {
     histogram: HashMap<T, C>,
+    /// The sum of the counts, kept up to date by every update so that `total_count()` is O(1),
+    /// or `None` if it has overflowed `C`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    total: Option<C>,
+}
+
+/// A `HashHistogram` counting with `usize`.
+pub type Histogram<T> = HashHistogram<T, usize>;
+
+impl<T: KeyType> HashHistogram<T, usize> {
+    /// Counts `items`, with `usize` counts.
+    pub fn counted<I: IntoIterator<Item = T>>(items: I) -> Self {
+        items.into_iter().collect()
+    }
+}
+
+impl<T: KeyType, C: CounterType> Default for HashHistogram<T, C> {
+    fn default() -> Self {
+        HashHistogram {
+            histogram: HashMap::new(),
+            total: Some(num::zero()),
+        }
+    }
+}
+
+impl<T: KeyType, C: CounterType> PartialEq for HashHistogram<T, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.histogram == other.histogram
+    }
+}
+
+impl<T: KeyType, C: CounterType> Eq for HashHistogram<T, C> {}
+
+/// The serialized form of `HashHistogram`, from which the total is recomputed.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct HistogramFields<T: KeyType, C: CounterType> {
+    histogram: HashMap<T, C>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: KeyType, C: CounterType> From<HistogramFields<T, C>> for HashHistogram<T, C> {
+    fn from(fields: HistogramFields<T, C>) -> Self {
+        HashHistogram::from(fields.histogram)
+    }
 }
 
 impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
@@ -129,11 +325,30 @@ impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
         HashHistogram::default()
     }
 
+    /// Creates a histogram in which each of `labels` is registered with a count of zero, so
+    /// that it appears in `iter()` and `len()` before it is ever bumped.
+    pub fn with_labels<I: IntoIterator<Item = T>>(labels: I) -> Self {
+        HashHistogram {
+            histogram: labels
+                .into_iter()
+                .map(|label| (label, num::zero()))
+                .collect(),
+            total: Some(num::zero()),
+        }
+    }
+
+    /// Registered labels that have never been bumped, such as labels from `with_labels()` whose
+    /// counts remain zero.
+    pub fn untouched_since_baseline(&self) -> Vec<T> {
+        self.keys_with_count(num::zero())
+    }
+
     pub fn bump(&mut self, item: &T) {
         self.bump_by(item, num::one());
     }
 
     pub fn bump_by(&mut self, item: &T, increment: C) {
+        self.add_to_total(increment);
         match self.histogram.get_mut(item) {
             None => {
                 self.histogram.insert(item.clone(), increment);
@@ -144,22 +359,158 @@ impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
         };
     }
 
+    /// Like `bump()`, but takes ownership of `item`, so it is never cloned.
+    pub fn bump_owned(&mut self, item: T) {
+        self.bump_by_owned(item, num::one());
+    }
+
+    /// Like `bump_by()`, but takes ownership of `item`, so it is never cloned. Uses a single
+    /// hash lookup whether or not the key is already present.
+    pub fn bump_by_owned(&mut self, item: T, increment: C) {
+        self.add_to_total(increment);
+        *self.histogram.entry(item).or_insert(num::zero()) += increment;
+    }
+
+    pub fn bump_all<'a, I: IntoIterator<Item = &'a T>>(&mut self, items: I)
+    where
+        T: 'a,
+    {
+        for item in items {
+            self.bump(item);
+        }
+    }
+
+    /// Adds each count to the corresponding key, as when loading pre-aggregated data.
+    pub fn bump_pairs<'a, I: IntoIterator<Item = (&'a T, C)>>(&mut self, pairs: I)
+    where
+        T: 'a,
+    {
+        for (item, increment) in pairs {
+            self.bump_by(item, increment);
+        }
+    }
+
+    /// Adds `increment` to the count of `item`, stopping at the largest value of `C` instead of
+    /// overflowing.
+    pub fn saturating_bump_by(&mut self, item: &T, increment: C)
+    where
+        C: SaturatingAdd,
+    {
+        match self.histogram.get_mut(item) {
+            None => self.bump_by(item, increment),
+            Some(count) => {
+                let updated = count.saturating_add(&increment);
+                let added = updated - *count;
+                *count = updated;
+                self.add_to_total(added);
+            }
+        }
+    }
+
+    /// Adds `increment` to the count of `item` and returns the new count, unless doing so would
+    /// overflow, in which case the count is left unchanged and `None` is returned.
+    pub fn checked_bump_by(&mut self, item: &T, increment: C) -> Option<C> {
+        let updated = self.count(item).checked_add(&increment)?;
+        if updated != num::zero() {
+            self.histogram.insert(item.clone(), updated);
+            self.add_to_total(increment);
+        }
+        Some(updated)
+    }
+
     pub fn len(&self) -> usize {
         self.histogram.len()
     }
 
-    pub fn count(&self, item: &T) -> C {
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    /// As with `HashMap::get()`, `item` may be any borrowed form of the key type, such as a
+    /// `&str` for `String` keys.
+    pub fn count<Q: Hash + Eq + ?Sized>(&self, item: &Q) -> C
+    where
+        T: Borrow<Q>,
+    {
         *self.histogram.get(item).unwrap_or(&num::zero())
     }
 
-    pub fn iter(&self) -> Iter<T, C> {
+    /// Whether `item` is a key, even one with a count of zero such as a label from
+    /// `with_labels()`.
+    pub fn contains<Q: Hash + Eq + ?Sized>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+    {
+        self.histogram.contains_key(item)
+    }
+
+    /// Removes `item`, returning its count if it was present.
+    pub fn remove<Q: Hash + Eq + ?Sized>(&mut self, item: &Q) -> Option<C>
+    where
+        T: Borrow<Q>,
+    {
+        let removed = self.histogram.remove(item)?;
+        self.total = self.total.map(|total| total - removed);
+        Some(removed)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, C> {
         self.histogram.iter()
     }
 
+    /// The underlying map from keys to counts.
+    pub fn as_map(&self) -> &HashMap<T, C> {
+        &self.histogram
+    }
+
+    /// Consumes the histogram, returning the underlying map without copying it.
+    pub fn into_inner(self) -> HashMap<T, C> {
+        self.histogram
+    }
+
     pub fn counts(&self) -> impl Iterator<Item=C> + '_ {
         self.iter().map(|(_,c)| c).copied()
     }
 
+    /// The keys, borrowed from the histogram.
+    pub fn keys(&self) -> impl Iterator<Item = &T> + '_ {
+        self.histogram.keys()
+    }
+
+    /// Entries from highest to lowest count, in the same order as `ranking_with_counts()`.
+    /// Entries are produced lazily from a heap, so taking the first `k` of `n` entries takes
+    /// O(n + k log n) time.
+    pub fn iter_sorted_by_count(&self) -> impl Iterator<Item = (&T, C)> + '_ {
+        let entries: Vec<(&T, C)> = self.iter().map(|(k, c)| (k, *c)).collect();
+        let mut heap: BinaryHeap<(C, Reverse<usize>)> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, (_, c))| (*c, Reverse(i)))
+            .collect();
+        std::iter::from_fn(move || heap.pop().map(|(_, Reverse(i))| entries[i]))
+    }
+
+    /// Entries in the order of `iter_sorted_by_count()`, each with its share of the total count
+    /// and the cumulative share of the entries so far, for Pareto analysis.
+    pub fn iter_shares(&self) -> impl Iterator<Item = (&T, C, f64, f64)> + '_ {
+        let total = self.total_count().to_f64().unwrap();
+        let mut running: C = num::zero();
+        self.iter_sorted_by_count().map(move |(key, count)| {
+            running += count;
+            let share = count.to_f64().unwrap() / total;
+            (key, count, share, running.to_f64().unwrap() / total)
+        })
+    }
+
+    /// The keys, borrowed from the histogram. The same as `keys()`.
+    pub fn labels(&self) -> impl Iterator<Item = &T> + '_ {
+        self.keys()
+    }
+
+    #[deprecated(
+        since = "0.10.0",
+        note = "clones every key; use `labels()` and collect if a set is needed"
+    )]
     pub fn all_labels(&self) -> HashSet<T> {
         self.iter().map(|(k, _)| k.clone()).collect()
     }
@@ -177,35 +528,378 @@ impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
         ranking
     }
 
+    /// Empties the histogram, yielding each key and its count without cloning the keys.
+    pub fn drain(&mut self) -> Drain<'_, T, C> {
+        self.total = Some(num::zero());
+        self.histogram.drain()
+    }
+
+    /// Empties the histogram, yielding each key and its count from highest to lowest count, in
+    /// the same order as `ranking_with_counts()`, without cloning the keys.
+    pub fn drain_sorted_by_count(&mut self) -> std::vec::IntoIter<(T, C)> {
+        let mut ranking: Vec<(T, C)> = self.drain().collect();
+        ranking.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+        ranking.into_iter()
+    }
+
+    /// Keys in ascending order of `sort_key(key, count)`. Wrap the count in
+    /// `std::cmp::Reverse` to put the highest counts first.
+    pub fn ranking_by<K: Ord, F: FnMut(&T, C) -> K>(&self, sort_key: F) -> Vec<T> {
+        self.ranking_with_counts_by(sort_key)
+            .into_iter()
+            .map(|(k, _)| k)
+            .collect()
+    }
+
+    pub fn ranking_with_counts_by<K: Ord, F: FnMut(&T, C) -> K>(
+        &self,
+        mut sort_key: F,
+    ) -> Vec<(T, C)> {
+        let mut ranking: Vec<(T, C)> = self.iter().map(|(t, n)| (t.clone(), *n)).collect();
+        ranking.sort_by_cached_key(|(t, n)| sort_key(t, *n));
+        ranking
+    }
+
+    /// Calls `visit(key, count, rank)` for the `n` highest-ranked keys in order, without
+    /// allocating. Each step scans the whole histogram, so this suits small values of `n`.
+    pub fn for_each_ranked<F: FnMut(&T, C, usize)>(&self, n: usize, mut visit: F) {
+        let mut previous: Option<(C, usize)> = None;
+        for rank in 0..n.min(self.len()) {
+            let next = self
+                .histogram
+                .iter()
+                .enumerate()
+                .filter(|(i, (_, c))| match previous {
+                    None => true,
+                    Some((pc, pi)) => **c < pc || (**c == pc && *i > pi),
+                })
+                .max_by(|(i1, (_, c1)), (i2, (_, c2))| c1.cmp(c2).then_with(|| i2.cmp(i1)));
+            let (i, (key, count)) = next.unwrap();
+            visit(key, *count, rank);
+            previous = Some((*count, i));
+        }
+    }
+
+    /// Fills `buffer` with the highest-ranked keys and their counts, from highest to lowest,
+    /// without allocating, so a summary can be reported from a fixed-size buffer. Slots beyond
+    /// the number of keys are set to `(None, 0)`. Keys with tied counts appear in arbitrary order.
+    /// Returns the number of keys written.
+    pub fn report_into<'a>(&'a self, buffer: &mut [(Option<&'a T>, C)]) -> usize {
+        buffer.fill((None, num::zero()));
+        let mut written = 0;
+        for (key, count) in self.iter() {
+            let position = buffer[..written]
+                .iter()
+                .position(|(_, c)| count > c)
+                .unwrap_or(written);
+            if position < buffer.len() {
+                written = (written + 1).min(buffer.len());
+                buffer[position..written].rotate_right(1);
+                buffer[position] = (Some(key), *count);
+            }
+        }
+        written
+    }
+
+    pub fn rank_of<Q: Hash + Eq + ?Sized>(&self, item: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+    {
+        self.rank_of_with(item, RankTies::default())
+    }
+
+    pub fn rank_of_with<Q: Hash + Eq + ?Sized>(&self, item: &Q, ties: RankTies) -> Option<usize>
+    where
+        T: Borrow<Q>,
+    {
+        let count = *self.histogram.get(item)?;
+        Some(match ties {
+            RankTies::Competition => self.counts().filter(|c| *c > count).count(),
+            RankTies::Dense => self
+                .counts()
+                .filter(|c| *c > count)
+                .collect::<BTreeSet<C>>()
+                .len(),
+            RankTies::Modified => self.counts().filter(|c| *c >= count).count() - 1,
+        })
+    }
+
     pub fn mode(&self) -> Option<T> {
         self.iter()
             .max_by_key(|(_, count)| **count)
             .map(|(key, _)| key.clone())
     }
 
+    /// Like `mode()`, but also returns the mode's count.
+    pub fn mode_with_count(&self) -> Option<(T, C)> {
+        self.iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(key, count)| (key.clone(), *count))
+    }
+
+    /// The count of the mode. The same as `max_count()`.
+    pub fn mode_count(&self) -> Option<C> {
+        self.max_count()
+    }
+
+    /// Returns every key tied for the mode, along with their count. The same as `max_entry()`.
+    pub fn modes_with_count(&self) -> Option<(Vec<T>, C)> {
+        self.max_entry()
+    }
+
+    /// Takes constant time, as the total is maintained as counts change, unless the total has
+    /// overflowed `C`, in which case the counts are summed.
     pub fn total_count(&self) -> C {
-        self.iter().map(|(_, value)| value).copied().sum::<C>()
+        self.total
+            .unwrap_or_else(|| self.iter().map(|(_, value)| value).copied().sum::<C>())
+    }
+
+    fn add_to_total(&mut self, increment: C) {
+        self.total = self.total.and_then(|total| total.checked_add(&increment));
+    }
+
+    pub fn max_count(&self) -> Option<C> {
+        self.counts().max()
+    }
+
+    pub fn min_count(&self) -> Option<C> {
+        self.counts().min()
+    }
+
+    /// Returns every key tied for the largest count, along with that count.
+    pub fn max_entry(&self) -> Option<(Vec<T>, C)> {
+        self.max_count()
+            .map(|count| (self.keys_with_count(count), count))
+    }
+
+    /// Returns every key tied for the smallest count, along with that count.
+    pub fn min_entry(&self) -> Option<(Vec<T>, C)> {
+        self.min_count()
+            .map(|count| (self.keys_with_count(count), count))
+    }
+
+    /// Replaces the count of every key found in either histogram with `f(self.count(key),
+    /// other.count(key))`. Keys whose combined count is zero are removed.
+    pub fn merge_with<F: Fn(C, C) -> C>(&mut self, other: &Self, f: F) {
+        for (key, count) in self.histogram.iter_mut() {
+            *count = f(*count, other.count(key));
+        }
+        for (key, count) in other.iter() {
+            if !self.histogram.contains_key(key) {
+                self.histogram.insert(key.clone(), f(num::zero(), *count));
+            }
+        }
+        self.histogram.retain(|_, count| *count != num::zero());
+        self.total = checked_total(&self.histogram);
+    }
+
+    /// Multiset union: each key's count is the larger of its counts in the two histograms.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.merge_with(other, |c1, c2| c1.max(c2));
+        result
+    }
+
+    /// Multiset intersection: each key's count is the smaller of its counts in the two histograms.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut result = self.clone();
+        result.merge_with(other, |c1, c2| c1.min(c2));
+        result
+    }
+
+    /// Returns `true` if no key has a larger count in `self` than in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.iter().all(|(key, count)| *count <= other.count(key))
+    }
+
+    pub fn is_superset(&self, other: &Self) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Multiplies every count by `factor`. Scaling by zero empties the histogram.
+    pub fn scale_by(&mut self, factor: C) {
+        for count in self.histogram.values_mut() {
+            *count = *count * factor;
+        }
+        self.histogram.retain(|_, count| *count != num::zero());
+        self.total = checked_total(&self.histogram);
+    }
+
+    /// Returns a histogram with `f` applied to every count. Keys mapped to zero are omitted.
+    pub fn map_counts<F: Fn(C) -> C>(&self, f: F) -> Self {
+        let histogram = self
+            .iter()
+            .map(|(key, count)| (key.clone(), f(*count)))
+            .filter(|(_, count)| *count != num::zero())
+            .collect::<HashMap<T, C>>();
+        HashHistogram::from(histogram)
+    }
+
+    /// Returns a histogram with every key replaced by `f(key)`. Keys mapped to the same key have
+    /// their counts added, as when lowercasing words or truncating timestamps to the hour.
+    pub fn map_keys<U: KeyType, F: Fn(&T) -> U>(&self, f: F) -> HashHistogram<U, C> {
+        let mut result = HashHistogram::new();
+        for (key, count) in self.iter() {
+            result.bump_by_owned(f(key), *count);
+        }
+        result
+    }
+
+    /// Returns a histogram of the entries for which `predicate(key, count)` is true.
+    pub fn filtered<F: FnMut(&T, C) -> bool>(&self, mut predicate: F) -> Self {
+        self.iter()
+            .filter(|(key, count)| predicate(key, **count))
+            .map(|(key, count)| (key.clone(), *count))
+            .collect::<HashMap<T, C>>()
+            .into()
+    }
+
+    /// Splits the entries into those for which `predicate(key, count)` is true and those for
+    /// which it is false.
+    pub fn partition<F: FnMut(&T, C) -> bool>(&self, mut predicate: F) -> (Self, Self) {
+        let (matching, rest): (HashMap<T, C>, HashMap<T, C>) = self
+            .iter()
+            .map(|(key, count)| (key.clone(), *count))
+            .partition(|(key, count)| predicate(key, *count));
+        (matching.into(), rest.into())
+    }
+
+    /// The frequency of frequencies: how many keys have each count.
+    pub fn counts_of_counts(&self) -> HashHistogram<C, usize>
+    where
+        C: Hash,
+    {
+        self.iter().map(|(_, count)| *count).collect()
+    }
+
+    /// Expands the histogram into individual observations, with each key repeated by its count.
+    /// Keys appear in sorted order. Panics if a count does not fit in a `usize`.
+    pub fn to_sample_vec(&self) -> Vec<T>
+    where
+        T: Ord,
+    {
+        let mut keys: Vec<(&T, C)> = self.iter().map(|(k, c)| (k, *c)).collect();
+        keys.sort_by_key(|(key, _)| *key);
+        Self::expand(keys)
+    }
+
+    /// As `to_sample_vec()`, but with keys in ranked order. Keys with equal counts appear in
+    /// sorted order.
+    pub fn to_sample_vec_ranked(&self) -> Vec<T>
+    where
+        T: Ord,
+    {
+        let mut keys: Vec<(&T, C)> = self.iter().map(|(k, c)| (k, *c)).collect();
+        keys.sort_by(|(k1, c1), (k2, c2)| c2.cmp(c1).then_with(|| k1.cmp(k2)));
+        Self::expand(keys)
+    }
+
+    fn expand(keys: Vec<(&T, C)>) -> Vec<T> {
+        keys.into_iter()
+            .flat_map(|(key, count)| {
+                let count = count.to_usize().expect("count too large to expand");
+                std::iter::repeat_n(key.clone(), count)
+            })
+            .collect()
+    }
+
+    /// Formats like `Display`, but with the `Debug` format of each key, so it is available for
+    /// keys that do not implement `Display`.
+    pub fn to_string_by_count(&self) -> String
+    where
+        T: Debug,
+        C: fmt::Display,
+    {
+        self.display_order()
+            .iter()
+            .map(|(label, count)| format!("{:?}:{}; ", label, count))
+            .collect()
+    }
+
+    fn display_order(&self) -> Vec<(&T, C)>
+    where
+        T: Debug,
+    {
+        let mut in_order: Vec<(&T, String, C)> = self
+            .iter()
+            .map(|(k, c)| (k, format!("{:?}", k), *c))
+            .collect();
+        in_order.sort_by(|(_, d1, c1), (_, d2, c2)| c2.cmp(c1).then_with(|| d1.cmp(d2)));
+        in_order.into_iter().map(|(k, _, c)| (k, c)).collect()
+    }
+
+    fn keys_with_count(&self, count: C) -> Vec<T> {
+        self.iter()
+            .filter(|(_, c)| **c == count)
+            .map(|(k, _)| k.clone())
+            .collect()
     }
 }
 
-impl<T: KeyType + std::cmp::Ord + fmt::Display, C: CounterType + fmt::Display> fmt::Display
+/// Lists keys from highest to lowest count, breaking ties by the `Debug` format of the keys.
+impl<T: KeyType + Debug + fmt::Display, C: CounterType + fmt::Display> fmt::Display
     for HashHistogram<T, C>
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut in_order: Vec<T> = self.iter().map(|(k, _)| k).cloned().collect();
-        in_order.sort();
-        for label in in_order {
-            write!(f, "{}:{}; ", label, self.count(&label))?;
+        for (label, count) in self.display_order() {
+            write!(f, "{}:{}; ", label, count)?;
         }
         Ok(())
     }
 }
 
+impl<T: KeyType, C: CounterType> Mul<C> for HashHistogram<T, C> {
+    type Output = Self;
+
+    fn mul(mut self, factor: C) -> Self {
+        self.scale_by(factor);
+        self
+    }
+}
+
+/// Adds the counts of all the histograms, so map-reduce style partial histograms can be merged
+/// with `sum()`.
+impl<T: KeyType, C: CounterType> Sum for HashHistogram<T, C> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let mut result = HashHistogram::new();
+        for histogram in iter {
+            result.extend(histogram.into_inner());
+        }
+        result
+    }
+}
+
+impl<'a, T: 'a + KeyType, C: 'a + CounterType> Sum<&'a HashHistogram<T, C>>
+    for HashHistogram<T, C>
+{
+    fn sum<I: Iterator<Item = &'a HashHistogram<T, C>>>(iter: I) -> Self {
+        let mut result = HashHistogram::new();
+        for histogram in iter {
+            result.bump_pairs(histogram.iter().map(|(key, count)| (key, *count)));
+        }
+        result
+    }
+}
+
+/// Wraps the map without copying it, summing its counts once.
+impl<T: KeyType, C: CounterType> From<HashMap<T, C>> for HashHistogram<T, C> {
+    fn from(histogram: HashMap<T, C>) -> Self {
+        let total = checked_total(&histogram);
+        HashHistogram { histogram, total }
+    }
+}
+
+impl<T: KeyType, C: CounterType> From<HashHistogram<T, C>> for HashMap<T, C> {
+    fn from(histogram: HashHistogram<T, C>) -> Self {
+        histogram.into_inner()
+    }
+}
+
 impl<T: KeyType, C: CounterType> FromIterator<T> for HashHistogram<T, C> {
     fn from_iter<V: IntoIterator<Item = T>>(iter: V) -> Self {
         let mut result = HashHistogram::new();
         for value in iter {
-            result.bump(&value);
+            result.bump_owned(value);
         }
         result
     }
@@ -221,18 +915,55 @@ impl<'a, T: 'a + KeyType, C: 'a + CounterType> FromIterator<&'a T> for HashHisto
     }
 }
 
+impl<T: KeyType, C: CounterType> FromIterator<(T, C)> for HashHistogram<T, C> {
+    fn from_iter<V: IntoIterator<Item = (T, C)>>(iter: V) -> Self {
+        let mut result = HashHistogram::new();
+        result.extend(iter);
+        result
+    }
+}
+
 impl<'a, T: 'a + KeyType, C: 'a + CounterType> Extend<&'a T> for HashHistogram<T, C> {
     fn extend<V: IntoIterator<Item = &'a T>>(&mut self, iter: V) {
-        for value in iter {
-            self.bump(value);
+        self.bump_all(iter);
+    }
+}
+
+impl<T: KeyType, C: CounterType> Extend<(T, C)> for HashHistogram<T, C> {
+    fn extend<V: IntoIterator<Item = (T, C)>>(&mut self, iter: V) {
+        for (value, count) in iter {
+            self.bump_by_owned(value, count);
         }
     }
 }
 
-// Future idea:
-//
-// https://stackoverflow.com/questions/30540766/how-can-i-add-new-methods-to-iterator
-//
+/// Adds `histogram()` and `mode()` to every iterator whose items can be collected into a
+/// `HashHistogram`, whether they are keys or references to keys.
+pub trait HistogramIterator: Iterator + Sized {
+    fn histogram<T: KeyType>(self) -> HashHistogram<T>
+    where
+        HashHistogram<T>: FromIterator<Self::Item>,
+    {
+        self.collect()
+    }
+
+    fn mode<T: KeyType>(self) -> Option<T>
+    where
+        HashHistogram<T>: FromIterator<Self::Item>,
+    {
+        self.histogram().mode()
+    }
+}
+
+impl<I: Iterator> HistogramIterator for I {}
+
+/// The sum of the counts, or `None` if it overflows `C`.
+fn checked_total<T, C: CounterType>(histogram: &HashMap<T, C>) -> Option<C> {
+    histogram
+        .values()
+        .try_fold(num::zero(), |total: C, count| total.checked_add(count))
+}
+
 pub fn mode<'a, T: 'a + KeyType, A: IntoIterator<Item = &'a T>>(container: A) -> Option<T> {
     container
         .into_iter()
@@ -240,6 +971,11 @@ pub fn mode<'a, T: 'a + KeyType, A: IntoIterator<Item = &'a T>>(container: A) ->
         .mode()
 }
 
+/// Counts `items`, with `usize` counts.
+pub fn histogram_of<T: KeyType, A: IntoIterator<Item = T>>(items: A) -> Histogram<T> {
+    HashHistogram::counted(items)
+}
+
 pub fn mode_values<T: KeyType, A: IntoIterator<Item = T>>(container: A) -> Option<T> {
     container
         .into_iter()
@@ -252,6 +988,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(deprecated)]
     fn test_hist() {
         let mut hist = HashHistogram::<_, usize>::new();
         let zeros = 10;
@@ -277,4 +1014,417 @@ mod tests {
         assert_eq!(2, hist.mode().unwrap());
         assert_eq!(zeros + ones + twos, hist.total_count());
     }
+
+    #[test]
+    fn test_extremes() {
+        let mut hist: HashHistogram<&str> = ["a", "b", "a", "c", "b", "d"].iter().collect();
+        assert_eq!(hist.max_count(), Some(2));
+        assert_eq!(hist.min_count(), Some(1));
+
+        let (mut max_keys, max) = hist.max_entry().unwrap();
+        max_keys.sort();
+        assert_eq!((max_keys, max), (vec!["a", "b"], 2));
+
+        let (mut min_keys, min) = hist.min_entry().unwrap();
+        min_keys.sort();
+        assert_eq!((min_keys, min), (vec!["c", "d"], 1));
+
+        hist.bump(&"c");
+        hist.bump(&"c");
+        assert_eq!(hist.max_entry(), Some((vec!["c"], 3)));
+
+        let empty = HashHistogram::<&str>::new();
+        assert_eq!(empty.max_count(), None);
+        assert_eq!(empty.min_entry(), None);
+    }
+
+    #[test]
+    fn test_rank_of() {
+        let hist: HashHistogram<&str> = ["a", "a", "a", "b", "b", "c", "c", "d"].iter().collect();
+        assert_eq!(hist.rank_of(&"a"), Some(0));
+        assert_eq!(hist.rank_of(&"b"), Some(1));
+        assert_eq!(hist.rank_of(&"c"), Some(1));
+        assert_eq!(hist.rank_of(&"d"), Some(3));
+        assert_eq!(hist.rank_of(&"e"), None);
+
+        assert_eq!(hist.rank_of_with(&"c", RankTies::Dense), Some(1));
+        assert_eq!(hist.rank_of_with(&"d", RankTies::Dense), Some(2));
+        assert_eq!(hist.rank_of_with(&"b", RankTies::Modified), Some(2));
+        assert_eq!(hist.rank_of_with(&"a", RankTies::Modified), Some(0));
+    }
+
+    #[test]
+    fn test_merge_with() {
+        let shard1: HashHistogram<&str> = ["a", "a", "b", "c"].iter().collect();
+        let shard2: HashHistogram<&str> = ["a", "b", "b", "b", "d"].iter().collect();
+
+        let mut maxima = shard1.clone();
+        maxima.merge_with(&shard2, |c1, c2| c1.max(c2));
+        let mut expected: HashHistogram<&str> = HashHistogram::new();
+        for (s, c) in [("a", 2), ("b", 3), ("c", 1), ("d", 1)].iter() {
+            expected.bump_by(s, *c);
+        }
+        assert_eq!(maxima, expected);
+
+        let mut minima = shard1.clone();
+        minima.merge_with(&shard2, |c1, c2| c1.min(c2));
+        let mut minima = minima.ranking_with_counts();
+        minima.sort();
+        assert_eq!(minima, vec![("a", 1), ("b", 1)]);
+
+        let mut sums = shard1;
+        sums.merge_with(&shard2, |c1, c2| c1 + c2);
+        assert_eq!(sums.total_count(), 9);
+    }
+
+    #[test]
+    fn test_multiset_operations() {
+        let h1: HashHistogram<char> = "aabbbc".chars().collect();
+        let h2: HashHistogram<char> = "abbbbd".chars().collect();
+
+        let union = h1.union(&h2);
+        assert_eq!(union, "aabbbbcd".chars().collect());
+        let intersection = h1.intersection(&h2);
+        assert_eq!(intersection, "abbb".chars().collect());
+
+        assert!(intersection.is_subset(&h1) && intersection.is_subset(&h2));
+        assert!(union.is_superset(&h1) && union.is_superset(&h2));
+        assert!(!h1.is_subset(&h2) && !h1.is_superset(&h2));
+        assert!(HashHistogram::new().is_subset(&h1));
+    }
+
+    #[test]
+    fn test_scaling() {
+        let mut h: HashHistogram<char, u16> = "abbccc".chars().collect();
+        h.scale_by(2);
+        assert_eq!(h.ranking_with_counts(), vec![('c', 6), ('b', 4), ('a', 2)]);
+
+        let tripled = h.clone() * 3;
+        assert_eq!(tripled.count(&'c'), 18);
+
+        let halved = h.map_counts(|c| c / 4);
+        assert_eq!(halved.count(&'c'), 1);
+        assert_eq!(halved.count(&'b'), 1);
+        assert_eq!(halved.len(), 2);
+
+        h.scale_by(0);
+        assert_eq!(h, HashHistogram::new());
+    }
+
+    #[test]
+    fn test_overflow_safe_bumps() {
+        let mut h = HashHistogram::<&str, u8>::new();
+        h.saturating_bump_by(&"a", 200);
+        h.saturating_bump_by(&"a", 100);
+        assert_eq!(h.count(&"a"), 255);
+
+        assert_eq!(h.checked_bump_by(&"b", 250), Some(250));
+        assert_eq!(h.checked_bump_by(&"b", 5), Some(255));
+        assert_eq!(h.checked_bump_by(&"b", 1), None);
+        assert_eq!(h.count(&"b"), 255);
+        assert_eq!(h.checked_bump_by(&"c", 0), Some(0));
+        assert_eq!(h.len(), 2);
+    }
+
+    #[test]
+    fn test_bulk_bumps() {
+        let mut h = HashHistogram::<String>::new();
+        let words: Vec<String> = "the cat and the hat".split(' ').map(String::from).collect();
+        h.bump_all(&words);
+        assert_eq!(h.count(&"the".to_string()), 2);
+
+        let (cat, dog) = ("cat".to_string(), "dog".to_string());
+        h.bump_pairs([(&cat, 4), (&dog, 2)]);
+        assert_eq!(h.count(&cat), 5);
+        assert_eq!(h.count(&dog), 2);
+        assert_eq!(h.total_count(), 11);
+    }
+
+    #[test]
+    fn test_collect_pairs() {
+        let rows = vec![("GET".to_string(), 10u32), ("POST".to_string(), 3)];
+        let mut h: HashHistogram<String, u32> = rows.into_iter().collect();
+        assert_eq!(h.count(&"GET".to_string()), 10);
+
+        h.extend([("POST".to_string(), 2), ("PUT".to_string(), 1)]);
+        assert_eq!(h.count(&"POST".to_string()), 5);
+        assert_eq!(h.total_count(), 16);
+    }
+
+    #[test]
+    fn test_iterator_adapters() {
+        let words = ["x", "y", "x"];
+        let h = words.iter().histogram();
+        assert_eq!(h.count(&"x"), 2);
+        assert_eq!(words.iter().mode(), Some("x"));
+        assert_eq!(words.iter().map(|w| w.len()).mode(), Some(1));
+        assert_eq!("abcbc".chars().filter(|c| *c != 'c').histogram().len(), 2);
+    }
+
+    #[test]
+    fn test_counts_of_counts() {
+        let h: HashHistogram<char> = "mississippi".chars().collect();
+        let signature = h.counts_of_counts();
+        assert_eq!(signature.count(&4), 2);
+        assert_eq!(signature.count(&2), 1);
+        assert_eq!(signature.count(&1), 1);
+        assert_eq!(signature.total_count(), h.len());
+    }
+
+    #[test]
+    fn test_display_by_count() {
+        #[derive(Debug, Hash, Clone, Eq, PartialEq, Default)]
+        struct Point(i32, i32);
+
+        let mut h: HashHistogram<Point> = HashHistogram::new();
+        h.bump_by(&Point(2, 0), 3);
+        h.bump(&Point(1, 5));
+        h.bump(&Point(0, 7));
+        assert_eq!(
+            h.to_string_by_count(),
+            "Point(2, 0):3; Point(0, 7):1; Point(1, 5):1; "
+        );
+
+        let words: HashHistogram<&str> = ["b", "c", "a", "c"].iter().collect();
+        assert_eq!(words.to_string(), "c:2; a:1; b:1; ");
+    }
+
+    #[test]
+    fn test_keys_without_debug() {
+        #[derive(Hash, Clone, Eq, PartialEq, Default)]
+        struct Opaque(u8);
+
+        let h: HashHistogram<Opaque> = [Opaque(1), Opaque(2), Opaque(1)].into_iter().collect();
+        assert_eq!(h.count(&Opaque(1)), 2);
+        assert!(h.mode() == Some(Opaque(1)));
+    }
+
+    #[test]
+    fn test_sample_vec() {
+        let h: HashHistogram<char, u8> = "abacb".chars().collect();
+        assert_eq!(h.to_sample_vec(), vec!['a', 'a', 'b', 'b', 'c']);
+        assert_eq!(h.to_sample_vec_ranked(), vec!['a', 'a', 'b', 'b', 'c']);
+        let h: HashHistogram<char, u8> = "abcbc".chars().collect();
+        assert_eq!(h.to_sample_vec_ranked(), vec!['b', 'b', 'c', 'c', 'a']);
+        let round_trip: HashHistogram<char, u8> = h.to_sample_vec().into_iter().collect();
+        assert_eq!(round_trip, h);
+    }
+
+    #[test]
+    fn test_ranking_by() {
+        use std::cmp::Reverse;
+
+        let h: HashHistogram<&str> = ["bb", "a", "ccc", "a", "dd", "bb"].iter().collect();
+        let ranked = h.ranking_by(|k, c| (Reverse(c), k.len(), *k));
+        assert_eq!(ranked, vec!["a", "bb", "dd", "ccc"]);
+        let by_length = h.ranking_with_counts_by(|k, _| Reverse(k.len()));
+        assert_eq!(by_length[0], ("ccc", 1));
+    }
+
+    #[test]
+    fn test_for_each_ranked() {
+        let h: HashHistogram<char> = "aabbbcdd".chars().collect();
+        let mut visited = Vec::new();
+        h.for_each_ranked(10, |key, count, rank| visited.push((*key, count, rank)));
+        assert_eq!(visited.len(), 4);
+        assert_eq!(visited[0], ('b', 3, 0));
+        assert_eq!(visited[3], ('c', 1, 3));
+        let mut middle: Vec<char> = visited[1..3].iter().map(|(k, _, _)| *k).collect();
+        middle.sort();
+        assert_eq!(middle, vec!['a', 'd']);
+
+        let mut count = 0;
+        h.for_each_ranked(2, |_, _, _| count += 1);
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_hash_map_conversions() {
+        let map = HashMap::from([("a", 2u32), ("b", 5)]);
+        let h = HashHistogram::from(map.clone());
+        assert_eq!(h.count(&"b"), 5);
+        assert_eq!(h.as_map(), &map);
+        let back: HashMap<&str, u32> = h.clone().into();
+        assert_eq!(back, map);
+        assert_eq!(h.into_inner(), map);
+    }
+
+    #[test]
+    fn test_untouched_labels() {
+        let mut h = HashHistogram::<&str>::with_labels(["unit", "integration", "fuzz"]);
+        assert_eq!(h.len(), 3);
+        h.bump(&"unit");
+        h.bump_by(&"integration", 4);
+        assert_eq!(h.untouched_since_baseline(), vec!["fuzz"]);
+        h.bump(&"fuzz");
+        assert!(h.untouched_since_baseline().is_empty());
+    }
+
+    #[test]
+    fn test_keys_and_sorted_iteration() {
+        let h: HashHistogram<u32> = [4, 1, 4, 2, 4, 1, 3].iter().collect();
+        let mut keys: Vec<&u32> = h.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec![&1, &2, &3, &4]);
+        let sorted: Vec<(u32, usize)> = h.iter_sorted_by_count().map(|(k, c)| (*k, c)).collect();
+        assert_eq!(sorted, h.ranking_with_counts());
+        assert_eq!(h.iter_sorted_by_count().next(), Some((&4, 3)));
+    }
+
+    #[test]
+    fn test_bump_owned() {
+        let mut h = HashHistogram::<String, u32>::new();
+        for word in "the cat saw the dog".split(' ') {
+            h.bump_owned(word.to_string());
+        }
+        h.bump_by_owned("dog".to_string(), 4);
+        assert_eq!(h.count(&"the".to_string()), 2);
+        assert_eq!(h.count(&"dog".to_string()), 5);
+        assert_eq!(h.len(), 4);
+    }
+
+    #[test]
+    fn test_iter_shares() {
+        let mut h = HashHistogram::<char>::new();
+        h.bump_by(&'a', 6);
+        h.bump_by(&'b', 3);
+        h.bump(&'c');
+        let shares: Vec<(&char, usize, f64, f64)> = h.iter_shares().collect();
+        assert_eq!(
+            shares,
+            vec![
+                (&'a', 6, 0.6, 0.6),
+                (&'b', 3, 0.3, 0.9),
+                (&'c', 1, 0.1, 1.0)
+            ]
+        );
+        let top_80: Vec<&char> = h
+            .iter_shares()
+            .take_while(|(_, _, share, cumulative)| cumulative - share < 0.8)
+            .map(|(key, _, _, _)| key)
+            .collect();
+        assert_eq!(top_80, vec![&'a', &'b']);
+    }
+
+    #[test]
+    fn test_borrowed_lookups() {
+        let mut h: HashHistogram<String> = ["a", "b", "b"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(h.count("b"), 2);
+        assert_eq!(h.rank_of("a"), Some(1));
+        assert_eq!(h.remove("b"), Some(2));
+        assert_eq!(h.remove("b"), None);
+        assert_eq!(h.count("b"), 0);
+        assert_eq!(h.len(), 1);
+    }
+
+    #[test]
+    fn test_is_empty_and_contains() {
+        let mut h = HashHistogram::<&str>::with_labels(["a"]);
+        assert!(!h.is_empty());
+        assert!(h.contains(&"a"));
+        assert!(!h.contains(&"b"));
+        h.remove(&"a");
+        assert!(h.is_empty());
+        assert!(HashHistogram::<u8>::new().is_empty());
+    }
+
+    #[test]
+    fn test_sum() {
+        let parts: Vec<HashHistogram<char>> = ["ab", "b", ""]
+            .iter()
+            .map(|s| s.chars().collect())
+            .collect();
+        let borrowed: HashHistogram<char> = parts.iter().sum();
+        assert_eq!(borrowed.count(&'b'), 2);
+        assert_eq!(borrowed, parts.into_iter().sum());
+        let none: HashHistogram<char> = std::iter::empty::<HashHistogram<char>>().sum();
+        assert_eq!(none, HashHistogram::default());
+    }
+
+    #[test]
+    fn test_cached_total() {
+        let mut h: HashHistogram<char, u8> = "aabbbc".chars().collect();
+        assert_eq!(h.total_count(), 6);
+        h.remove(&'b');
+        h.bump_by_owned('d', 4);
+        assert_eq!(h.total_count(), 7);
+        h.scale_by(2);
+        assert_eq!(h.total_count(), 14);
+        h.merge_with(&"ae".chars().collect(), |c1, c2| c1.max(c2));
+        assert_eq!(h.total_count(), 15);
+        assert_eq!(h.checked_bump_by(&'z', 241), Some(241));
+        h.remove(&'z');
+        assert_eq!(h.total_count(), 15);
+
+        #[cfg(feature = "serde")]
+        {
+            let h: HashHistogram<char> = "abb".chars().collect();
+            let serialized = serde_json::to_string(&h).unwrap();
+            assert!(serialized.starts_with("{\"histogram\":{"));
+            let restored: HashHistogram<char> = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(restored.total_count(), 3);
+            assert_eq!(restored, h);
+        }
+    }
+
+    #[test]
+    fn test_report_into() {
+        let h: HashHistogram<char> = "abbcccdddd".chars().collect();
+        let mut top = [(None, 0); 2];
+        assert_eq!(h.report_into(&mut top), 2);
+        assert_eq!(top, [(Some(&'d'), 4), (Some(&'c'), 3)]);
+        let mut all = [(Some(&'z'), 9); 6];
+        assert_eq!(h.report_into(&mut all), 4);
+        assert_eq!(all[3], (Some(&'a'), 1));
+        assert_eq!(all[4..], [(None, 0), (None, 0)]);
+        assert_eq!(h.report_into(&mut []), 0);
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut h: HashHistogram<String> = ["x", "y", "y"].iter().map(|s| s.to_string()).collect();
+        let drained: Vec<(String, usize)> = h.drain_sorted_by_count().collect();
+        assert_eq!(drained, vec![("y".to_string(), 2), ("x".to_string(), 1)]);
+        assert!(h.is_empty());
+        assert_eq!(h.total_count(), 0);
+        h.bump(&"z".to_string());
+        assert_eq!(h.drain().collect::<Vec<_>>(), vec![("z".to_string(), 1)]);
+        assert_eq!(h, HashHistogram::new());
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let h: HashHistogram<&str> = ["The", "the", "cat", "THE"].iter().collect();
+        let lower = h.map_keys(|word| word.to_lowercase());
+        assert_eq!(lower.count("the"), 3);
+        assert_eq!(lower.len(), 2);
+        assert_eq!(lower.total_count(), h.total_count());
+    }
+
+    #[test]
+    fn test_filtered_and_partition() {
+        let h: HashHistogram<&str> = ["err_io", "ok", "err_io", "err_auth", "ok"]
+            .iter()
+            .collect();
+        let errors = h.filtered(|key, _| key.starts_with("err_"));
+        assert_eq!(errors.total_count(), 3);
+        assert_eq!(errors.len(), 2);
+        let (frequent, rare) = h.partition(|_, count| count > 1);
+        assert!(frequent.contains(&"ok") && frequent.contains(&"err_io"));
+        assert_eq!(rare.ranking(), vec!["err_auth"]);
+        assert_eq!(frequent.total_count() + rare.total_count(), h.total_count());
+    }
+
+    #[test]
+    fn test_mode_with_count() {
+        let h: HashHistogram<char, u8> = "abcbcb".chars().collect();
+        assert_eq!(h.mode_with_count(), Some(('b', 3)));
+        assert_eq!(h.mode_count(), Some(3));
+        assert_eq!(h.modes_with_count(), Some((vec!['b'], 3)));
+        let tied: HashHistogram<char> = "abab".chars().collect();
+        let (mut keys, count) = tied.modes_with_count().unwrap();
+        keys.sort();
+        assert_eq!((keys, count), (vec!['a', 'b'], 2));
+        assert_eq!(HashHistogram::<char>::new().mode_with_count(), None);
+    }
 }
@@ -0,0 +1,187 @@
+//! # Logarithmic histograms
+//! `LogHistogram` counts unsigned integers, such as latencies in microseconds, in buckets whose
+//! width doubles with each power of two. Memory use is fixed no matter how many or how large the
+//! values are, and percentiles are accurate to within the width of a bucket.
+//!
+//! By default, the bucket of a value is determined by its number of leading zeros alone. Each
+//! power-of-two range can also be split into `2^precision` equal sub-buckets, which bounds the
+//! relative error of reported percentiles by `2^-precision`.
+//!
+//! ```
+//! use hash_histogram::LogHistogram;
+//!
+//! let mut latencies = LogHistogram::with_precision(3);
+//! for micros in 1..=1000 {
+//!     latencies.record(micros);
+//! }
+//! assert_eq!(latencies.total_count(), 1000);
+//! assert_eq!(latencies.percentile(0.0), Some(1));
+//! assert_eq!(latencies.percentile(50.0), Some(511));
+//! assert_eq!(latencies.percentile(100.0), Some(1000));
+//!
+//! let p99 = latencies.percentile(99.0).unwrap();
+//! assert!(p99 >= 990 && p99 <= 990 + 990 / 8);
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+const MAX_PRECISION: u32 = 12;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LogHistogram {
+    precision: u32,
+    counts: Vec<u64>,
+    total: u64,
+    min: u64,
+    max: u64,
+}
+
+impl Default for LogHistogram {
+    fn default() -> Self {
+        Self::with_precision(0)
+    }
+}
+
+impl LogHistogram {
+    /// One bucket per power of two.
+    pub fn new() -> Self {
+        LogHistogram::default()
+    }
+
+    /// Splits each power-of-two range into `2^precision` buckets. Panics if `precision` exceeds
+    /// 12.
+    pub fn with_precision(precision: u32) -> Self {
+        assert!(
+            precision <= MAX_PRECISION,
+            "precision must be at most {}",
+            MAX_PRECISION
+        );
+        let sub_buckets = 1usize << precision;
+        LogHistogram {
+            precision,
+            counts: vec![0; sub_buckets * (u64::BITS - precision + 1) as usize],
+            total: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    pub fn record(&mut self, value: u64) {
+        self.record_n(value, 1);
+    }
+
+    pub fn record_n(&mut self, value: u64, count: u64) {
+        if count > 0 {
+            let bucket = self.bucket_of(value);
+            self.counts[bucket] += count;
+            self.total += count;
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+    }
+
+    pub fn total_count(&self) -> u64 {
+        self.total
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        (self.total > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        (self.total > 0).then_some(self.max)
+    }
+
+    /// The smallest recorded value such that `p` percent of recorded values are no larger,
+    /// reported as the largest value in its bucket. Returns `None` if nothing has been recorded.
+    /// Panics unless `p` is between 0 and 100.
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        assert!(
+            (0.0..=100.0).contains(&p),
+            "percentile must be between 0 and 100"
+        );
+        if self.total == 0 {
+            return None;
+        }
+        let rank = ((p / 100.0 * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let value = *self.bucket_range(bucket).end();
+                return Some(value.clamp(self.min, self.max));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// The value ranges of the non-empty buckets, with their counts, in increasing order.
+    pub fn buckets(&self) -> impl Iterator<Item = (RangeInclusive<u64>, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| (self.bucket_range(bucket), *count))
+    }
+
+    fn bucket_of(&self, value: u64) -> usize {
+        let sub_buckets = 1u64 << self.precision;
+        if value < sub_buckets {
+            return value as usize;
+        }
+        let magnitude = u64::BITS - value.leading_zeros();
+        let shift = magnitude - self.precision - 1;
+        let sub_bucket = (value >> shift) - sub_buckets;
+        (sub_buckets * (shift as u64 + 1) + sub_bucket) as usize
+    }
+
+    fn bucket_range(&self, bucket: usize) -> RangeInclusive<u64> {
+        let sub_buckets = 1u64 << self.precision;
+        let bucket = bucket as u64;
+        if bucket < sub_buckets {
+            return bucket..=bucket;
+        }
+        let shift = bucket / sub_buckets - 1;
+        let low = (sub_buckets + bucket % sub_buckets) << shift;
+        low..=low + ((1u64 << shift) - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_cover_all_values() {
+        for precision in [0, 2, 5] {
+            let h = LogHistogram::with_precision(precision);
+            let mut expected_low = 0;
+            for bucket in 0..h.counts.len() {
+                let range = h.bucket_range(bucket);
+                assert_eq!(*range.start(), expected_low);
+                assert_eq!(h.bucket_of(*range.start()), bucket);
+                assert_eq!(h.bucket_of(*range.end()), bucket);
+                expected_low = range.end().wrapping_add(1);
+            }
+            assert_eq!(expected_low, 0);
+        }
+
+        let mut h = LogHistogram::new();
+        h.record(0);
+        h.record_n(u64::MAX, 3);
+        h.record(5);
+        assert_eq!(h.buckets().count(), 3);
+        assert_eq!(h.buckets().nth(1), Some((4..=7, 1)));
+        assert_eq!(h.percentile(50.0), Some(u64::MAX));
+        assert_eq!(h.percentile(20.0), Some(0));
+        assert_eq!(h.percentile(40.0), Some(7));
+        assert_eq!(LogHistogram::new().percentile(50.0), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! # Head and tail
+//! `head_tail_split()` separates the frequent head of a histogram from its long tail, choosing
+//! the cutoff at the elbow of the rank-frequency curve. Ranks and counts are both rescaled to
+//! `[0, 1]`, and the elbow is the ranked entry farthest below the straight line joining the
+//! largest and smallest counts; it begins the tail. Among keys with equal counts, the first is
+//! always farthest from the line, so tied keys are never split between head and tail.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let mut pages = HashHistogram::<&str>::new();
+//! pages.bump_by(&"/", 500);
+//! pages.bump_by(&"/login", 300);
+//! for page in ["/a", "/b", "/c", "/d", "/e", "/f"] {
+//!     pages.bump_by(&page, 10);
+//! }
+//! let split = pages.head_tail_split();
+//! assert_eq!(split.head, vec![("/", 500), ("/login", 300)]);
+//! assert_eq!(split.tail_len, 6);
+//! assert_eq!(split.tail_total, 60);
+//! assert_eq!(split.tail_max, Some(10));
+//! assert!((split.tail_share() - 60.0 / 860.0).abs() < 1e-12);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+
+/// The head entries of a histogram, from highest to lowest count, with summary statistics of
+/// the remaining tail.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HeadTailSplit<T, C> {
+    pub head: Vec<(T, C)>,
+    /// Number of keys in the tail.
+    pub tail_len: usize,
+    /// Sum of the counts in the tail.
+    pub tail_total: C,
+    /// Largest count in the tail, or `None` if the tail is empty.
+    pub tail_max: Option<C>,
+    /// Sum of all counts, head and tail.
+    pub total: C,
+}
+
+impl<T, C: CounterType> HeadTailSplit<T, C> {
+    /// Fraction of the total count in the tail.
+    pub fn tail_share(&self) -> f64 {
+        if self.total == num::zero() {
+            0.0
+        } else {
+            self.tail_total.to_f64().unwrap() / self.total.to_f64().unwrap()
+        }
+    }
+
+    /// Mean count of the keys in the tail.
+    pub fn tail_mean(&self) -> Option<f64> {
+        (self.tail_len > 0).then(|| self.tail_total.to_f64().unwrap() / self.tail_len as f64)
+    }
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// If no entry lies below the line, as when every count is equal, there is no elbow and
+    /// every entry is in the head.
+    pub fn head_tail_split(&self) -> HeadTailSplit<T, C> {
+        let ranked = self.ranking_with_counts();
+        let cutoff = elbow(&ranked).unwrap_or(ranked.len());
+        let mut head = ranked;
+        let tail = head.split_off(cutoff);
+        let tail_total = tail.iter().map(|(_, c)| *c).sum::<C>();
+        HeadTailSplit {
+            total: head.iter().map(|(_, c)| *c).sum::<C>() + tail_total,
+            tail_len: tail.len(),
+            tail_max: tail.first().map(|(_, c)| *c),
+            tail_total,
+            head,
+        }
+    }
+}
+
+/// The index of the elbow in counts sorted from highest to lowest.
+fn elbow<T, C: CounterType>(ranked: &[(T, C)]) -> Option<usize> {
+    let max = ranked.first()?.1.to_f64().unwrap();
+    let min = ranked.last()?.1.to_f64().unwrap();
+    if max == min {
+        return None;
+    }
+    let last = (ranked.len() - 1) as f64;
+    ranked
+        .iter()
+        .enumerate()
+        .map(|(i, (_, c))| {
+            let x = i as f64 / last;
+            let y = (c.to_f64().unwrap() - min) / (max - min);
+            (i, 1.0 - x - y)
+        })
+        .max_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+        .filter(|(_, d)| *d > 0.0)
+        .map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_edge_cases() {
+        let empty = HashHistogram::<u8>::new().head_tail_split();
+        assert_eq!(empty.head, vec![]);
+        assert_eq!(
+            (empty.tail_len, empty.tail_max, empty.tail_mean()),
+            (0, None, None)
+        );
+        assert_eq!(empty.tail_share(), 0.0);
+
+        let flat: HashHistogram<u8> = [1, 2, 3].iter().collect();
+        assert_eq!(flat.head_tail_split().head.len(), 3);
+        let concave: HashHistogram<u8> = [(1, 10), (2, 9), (3, 1)].into_iter().collect();
+        assert_eq!(concave.head_tail_split().tail_len, 0);
+        let tied: HashHistogram<u8> = [(1, 10), (2, 2), (3, 2), (4, 1)].into_iter().collect();
+        assert_eq!(tied.head_tail_split().tail_len, 3);
+
+        let mut zipf = HashHistogram::<u32>::new();
+        for rank in 1..=100 {
+            zipf.bump_by(&rank, 1000 / rank as usize);
+        }
+        let split = zipf.head_tail_split();
+        assert!(split.head.len() > 1 && split.head.len() < 20);
+        assert_eq!(split.head.len() + split.tail_len, 100);
+        assert!(split.tail_max.unwrap() < split.head.last().unwrap().1);
+        assert_eq!(split.total, zipf.total_count());
+    }
+}
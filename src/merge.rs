@@ -0,0 +1,222 @@
+//! # Merging persisted counts
+//! Histograms can be warm-started from aggregates persisted elsewhere: a `HashMap` or
+//! `BTreeMap` of counts, or rows of `(key, count)` pairs from a database query whose count
+//! column has some other numeric type. All of these merges check for overflow, and a failed merge
+//! leaves the histogram unchanged.
+//!
+//! ```
+//! use hash_histogram::{HashHistogram, MergeError};
+//! use std::collections::{BTreeMap, HashMap};
+//!
+//! let mut h = HashHistogram::<String, u32>::new();
+//! h.merge_map(&HashMap::from([("a".to_string(), 5)])).unwrap();
+//! h.merge_btree_map(&BTreeMap::from([("a".to_string(), 1), ("b".to_string(), 2)])).unwrap();
+//! assert_eq!(h.count(&"a".to_string()), 6);
+//!
+//! // Rows from a database whose count column is a signed 64-bit integer
+//! let rows: Vec<(&str, i64)> = vec![("b", 3), ("c", 7)];
+//! assert_eq!(h.merge_rows(rows), Ok(2));
+//! assert_eq!(h.count(&"c".to_string()), 7);
+//!
+//! assert_eq!(h.merge_rows(vec![("d", 1), ("e", -1)]), Err(MergeError::InvalidCount { row: 1 }));
+//! assert_eq!(h.count(&"d".to_string()), 0);
+//! ```
+//!
+//! `merge_all()` combines many histograms in one pass, reserving space for the union of their
+//! keys up front and reporting how much each source contributed. `merge_all_widened()` also
+//! converts to a wider counter type, so that compact partial histograms can be merged without
+//! overflow. For these, `row` in a `MergeError` is the position of the offending histogram.
+//!
+//! ```
+//! use hash_histogram::{merge_all, merge_all_widened, HashHistogram};
+//!
+//! let shards: Vec<HashHistogram<&str, u8>> = vec![
+//!     [("a", 200), ("b", 1)].into_iter().collect(),
+//!     [("a", 100)].into_iter().collect(),
+//! ];
+//! assert!(merge_all(&shards).is_err());
+//! let merged = merge_all_widened::<_, _, u32, _>(&shards).unwrap();
+//! assert_eq!(merged.histogram.count(&"a"), 300);
+//! assert_eq!(merged.contributions, vec![201, 100]);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use num::{NumCast, ToPrimitive};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// The reason a merge was rejected. `row` is the position of the offending entry in the input.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MergeError {
+    /// The count could not be represented by the counter type, for example because it was
+    /// negative.
+    InvalidCount { row: usize },
+    /// Adding the count would overflow the counter type.
+    Overflow { row: usize },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::InvalidCount { row } => write!(f, "invalid count in row {}", row),
+            MergeError::Overflow { row } => write!(f, "count overflow in row {}", row),
+        }
+    }
+}
+
+impl std::error::Error for MergeError {}
+
+/// The result of `merge_all()` and `merge_all_widened()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergedHistograms<T: KeyType, C: CounterType> {
+    pub histogram: HashHistogram<T, C>,
+    /// The total count of each source, in order.
+    pub contributions: Vec<C>,
+}
+
+pub fn merge_all<'a, T, C, I>(histograms: I) -> Result<MergedHistograms<T, C>, MergeError>
+where
+    T: 'a + KeyType,
+    C: 'a + CounterType,
+    I: IntoIterator<Item = &'a HashHistogram<T, C>>,
+{
+    merge_all_widened(histograms)
+}
+
+/// Like `merge_all()`, but converting each count to `C2` before adding.
+pub fn merge_all_widened<'a, T, C, C2, I>(
+    histograms: I,
+) -> Result<MergedHistograms<T, C2>, MergeError>
+where
+    T: 'a + KeyType,
+    C: 'a + CounterType,
+    C2: CounterType + From<C>,
+    I: IntoIterator<Item = &'a HashHistogram<T, C>>,
+{
+    let sources: Vec<&HashHistogram<T, C>> = histograms.into_iter().collect();
+    // The union is no larger than the sum of the sizes, so the map never needs to grow.
+    let mut merged: HashMap<T, C2> = HashMap::with_capacity(sources.iter().map(|h| h.len()).sum());
+    let mut contributions = Vec::with_capacity(sources.len());
+    for (row, source) in sources.into_iter().enumerate() {
+        let mut contribution = C2::zero();
+        for (key, count) in source.iter() {
+            let count = <C2 as From<C>>::from(*count);
+            let entry = merged.entry(key.clone()).or_insert(C2::zero());
+            *entry = entry
+                .checked_add(&count)
+                .ok_or(MergeError::Overflow { row })?;
+            contribution = contribution
+                .checked_add(&count)
+                .ok_or(MergeError::Overflow { row })?;
+        }
+        contributions.push(contribution);
+    }
+    Ok(MergedHistograms {
+        histogram: merged.into(),
+        contributions,
+    })
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    pub fn merge_map(&mut self, map: &HashMap<T, C>) -> Result<(), MergeError> {
+        self.try_merge(map.iter().map(|(key, count)| (key.clone(), Some(*count))))
+            .map(|_| ())
+    }
+
+    pub fn merge_btree_map(&mut self, map: &BTreeMap<T, C>) -> Result<(), MergeError> {
+        self.try_merge(map.iter().map(|(key, count)| (key.clone(), Some(*count))))
+            .map(|_| ())
+    }
+
+    /// Merges `(key, count)` rows, converting each key into `T` and each count into `C`.
+    /// Returns the number of rows merged.
+    pub fn merge_rows<K, V, I>(&mut self, rows: I) -> Result<usize, MergeError>
+    where
+        K: Into<T>,
+        V: ToPrimitive,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.try_merge(
+            rows.into_iter()
+                .map(|(key, count)| (key.into(), <C as NumCast>::from(count))),
+        )
+    }
+
+    fn try_merge<I: Iterator<Item = (T, Option<C>)>>(
+        &mut self,
+        rows: I,
+    ) -> Result<usize, MergeError> {
+        let mut merged: HashMap<T, C> = HashMap::new();
+        let mut row_count = 0;
+        for (row, (key, count)) in rows.enumerate() {
+            let count = count.ok_or(MergeError::InvalidCount { row })?;
+            let current = match merged.get(&key) {
+                Some(current) => *current,
+                None => self.count(&key),
+            };
+            let updated = current
+                .checked_add(&count)
+                .ok_or(MergeError::Overflow { row })?;
+            merged.insert(key, updated);
+            row_count += 1;
+        }
+        for (key, count) in merged {
+            if count != num::zero() {
+                let previous = self.histogram.insert(key, count).unwrap_or(num::zero());
+                self.add_to_total(count - previous);
+            }
+        }
+        Ok(row_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overflow_leaves_histogram_unchanged() {
+        let mut h = HashHistogram::<&str, u8>::new();
+        h.bump_by(&"x", 200);
+        let before = h.clone();
+        assert_eq!(
+            h.merge_rows(vec![("y", 5u64), ("x", 50), ("x", 10)]),
+            Err(MergeError::Overflow { row: 2 })
+        );
+        assert_eq!(h, before);
+        assert_eq!(
+            h.merge_rows(vec![("y", 300u64)]),
+            Err(MergeError::InvalidCount { row: 0 })
+        );
+        assert_eq!(h.merge_map(&HashMap::from([("x", 55)])), Ok(()));
+        assert_eq!(h.count(&"x"), 255);
+        assert_eq!(
+            h.merge_btree_map(&BTreeMap::from([("x", 1)])),
+            Err(MergeError::Overflow { row: 0 })
+        );
+        assert_eq!(
+            MergeError::Overflow { row: 0 }.to_string(),
+            "count overflow in row 0"
+        );
+    }
+
+    #[test]
+    fn test_merge_all() {
+        let parts: Vec<HashHistogram<char, u16>> = ["ab", "", "bc"]
+            .iter()
+            .map(|s| s.chars().collect())
+            .collect();
+        let merged = merge_all(&parts).unwrap();
+        assert_eq!(merged.histogram, parts.iter().sum());
+        assert_eq!(merged.contributions, vec![2, 0, 2]);
+        assert_eq!(merge_all::<char, u8, _>(&[]).unwrap().histogram.len(), 0);
+
+        let big = HashHistogram::<char, u8>::from_iter([('a', 255)]);
+        assert_eq!(
+            merge_all([&big, &big, &big]),
+            Err(MergeError::Overflow { row: 1 })
+        );
+        let widened = merge_all_widened::<_, _, u16, _>([&big, &big, &big]).unwrap();
+        assert_eq!(widened.histogram.total_count(), 765);
+    }
+}
@@ -0,0 +1,106 @@
+//! # Mode tracking
+//! `ModeTrackingHistogram` keeps its mode and largest count up to date as keys are bumped, so
+//! `mode()`, `max_count()`, and `mode_with_count()` take constant time instead of scanning every
+//! key. Since counts only grow, a bump can only replace the mode with the bumped key. Among keys
+//! tied for the largest count, the mode is the one that reached it first, except that the order in
+//! which counts were reached is unknown when converting from a `HashHistogram`.
+//!
+//! ```
+//! use hash_histogram::ModeTrackingHistogram;
+//!
+//! let mut h = ModeTrackingHistogram::<&str>::new();
+//! for s in ["a", "b", "b", "a", "c", "a"].iter() {
+//!     h.bump(s);
+//! }
+//! assert_eq!(h.mode(), Some("a"));
+//! assert_eq!(h.max_count(), Some(3));
+//! assert_eq!(h.count(&"b"), 2);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::ops::Deref;
+
+/// Reading methods of `HashHistogram` are available through `Deref`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModeTrackingHistogram<T: KeyType, C: CounterType = usize> {
+    histogram: HashHistogram<T, C>,
+    mode: Option<(T, C)>,
+}
+
+impl<T: KeyType, C: CounterType> ModeTrackingHistogram<T, C> {
+    pub fn new() -> Self {
+        ModeTrackingHistogram::default()
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, num::one());
+    }
+
+    pub fn bump_by(&mut self, item: &T, increment: C) {
+        self.histogram.bump_by(item, increment);
+        let count = self.histogram.count(item);
+        if self.mode.as_ref().is_none_or(|(_, max)| count > *max) {
+            self.mode = Some((item.clone(), count));
+        }
+    }
+
+    pub fn mode(&self) -> Option<T> {
+        self.mode.as_ref().map(|(key, _)| key.clone())
+    }
+
+    pub fn max_count(&self) -> Option<C> {
+        self.mode.as_ref().map(|(_, count)| *count)
+    }
+
+    pub fn mode_count(&self) -> Option<C> {
+        self.max_count()
+    }
+
+    pub fn mode_with_count(&self) -> Option<(T, C)> {
+        self.mode.clone()
+    }
+
+    pub fn into_histogram(self) -> HashHistogram<T, C> {
+        self.histogram
+    }
+}
+
+impl<T: KeyType, C: CounterType> Deref for ModeTrackingHistogram<T, C> {
+    type Target = HashHistogram<T, C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.histogram
+    }
+}
+
+/// Finds the mode with a single scan of the histogram. The histogram does not record which key
+/// reached the largest count first, so among tied keys the mode is arbitrary.
+impl<T: KeyType, C: CounterType> From<HashHistogram<T, C>> for ModeTrackingHistogram<T, C> {
+    fn from(histogram: HashHistogram<T, C>) -> Self {
+        let mode = histogram
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(key, count)| (key.clone(), *count));
+        ModeTrackingHistogram { histogram, mode }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracks_untracked_mode() {
+        let mut rng = crate::stats::test_rng(3);
+        let mut h = ModeTrackingHistogram::<u8, u32>::new();
+        for _ in 0..1000 {
+            h.bump_by(&((rng() * 20.0) as u8), (rng() * 3.0) as u32);
+            assert_eq!(h.max_count(), h.histogram.max_count());
+            assert_eq!(h.count(&h.mode().unwrap()), h.max_count().unwrap());
+            assert_eq!(h.mode_with_count(), h.mode().zip(h.max_count()));
+        }
+        let converted = ModeTrackingHistogram::from(h.clone().into_histogram());
+        assert_eq!(converted.max_count(), h.max_count());
+        assert_eq!(ModeTrackingHistogram::<u8>::new().mode(), None);
+    }
+}
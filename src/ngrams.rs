@@ -0,0 +1,68 @@
+//! # N-gram counting
+//! Counts every run of `n` consecutive items, as in bigram and trigram statistics. Use
+//! `count_ngrams()` with any iterator, `count_slice_ngrams()` to count windows of a slice, and
+//! `count_char_ngrams()` for character n-grams of a string.
+//!
+//! ```
+//! use hash_histogram::ngrams::{count_char_ngrams, count_ngrams};
+//!
+//! let bigrams = count_ngrams("to be or not to be".split_whitespace(), 2);
+//! assert_eq!(bigrams.count(&vec!["to", "be"]), 2);
+//! assert_eq!(bigrams.total_count(), 5);
+//!
+//! let trigrams = count_char_ngrams("banana", 3);
+//! assert_eq!(trigrams.count(&"ana".to_string()), 2);
+//! ```
+
+use crate::{HashHistogram, KeyType};
+use std::collections::VecDeque;
+
+/// Panics if `n` is zero.
+pub fn count_ngrams<T: KeyType, I: IntoIterator<Item = T>>(
+    items: I,
+    n: usize,
+) -> HashHistogram<Vec<T>> {
+    assert!(n > 0, "n-grams must have at least one item");
+    let mut result = HashHistogram::new();
+    let mut window = VecDeque::with_capacity(n);
+    for item in items {
+        if window.len() == n {
+            window.pop_front();
+        }
+        window.push_back(item);
+        if window.len() == n {
+            result.bump(&window.iter().cloned().collect());
+        }
+    }
+    result
+}
+
+/// Panics if `n` is zero.
+pub fn count_slice_ngrams<T: KeyType>(items: &[T], n: usize) -> HashHistogram<Vec<T>> {
+    assert!(n > 0, "n-grams must have at least one item");
+    items.windows(n).map(|window| window.to_vec()).collect()
+}
+
+/// Counts n-grams of characters rather than bytes. Panics if `n` is zero.
+pub fn count_char_ngrams(text: &str, n: usize) -> HashHistogram<String> {
+    count_ngrams(text.chars(), n)
+        .iter()
+        .map(|(chars, count)| (chars.iter().collect(), *count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ngram_variants_agree() {
+        let items = [1, 2, 1, 2, 1, 3];
+        let from_iter = count_ngrams(items, 2);
+        assert_eq!(from_iter, count_slice_ngrams(&items, 2));
+        assert_eq!(from_iter.count(&vec![1, 2]), 2);
+        assert_eq!(count_ngrams(items, 7).len(), 0);
+        let unigrams = count_char_ngrams("héé", 1);
+        assert_eq!(unigrams.count(&"é".to_string()), 2);
+    }
+}
@@ -0,0 +1,144 @@
+//! # Parallel construction and merging
+//! Large inputs can be counted on several threads at once. Each thread builds its own
+//! `HashHistogram` from a share of the input, and the per-thread histograms are then merged.
+//! Both operations use scoped standard library threads, one per available CPU.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let tokens: Vec<u32> = (0..100_000).map(|i| i % 7).collect();
+//! let h = HashHistogram::<u32>::par_from_slice(&tokens);
+//! assert_eq!(h.total_count(), 100_000);
+//!
+//! let shards = vec![h.clone(), h.clone(), h];
+//! let merged = HashHistogram::par_merge(shards);
+//! assert_eq!(merged.total_count(), 300_000);
+//! ```
+//!
+//! To distribute work, `shard_by()` splits a histogram into `n` histograms by key hash, so
+//! that each key lands in exactly one shard. `shard_of()` gives the shard that a key belongs to,
+//! for routing new observations to the same worker.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<char> = "mississippi".chars().collect();
+//! let shards = h.shard_by(3);
+//! assert_eq!(shards.len(), 3);
+//! assert_eq!(shards[HashHistogram::<char>::shard_of(&'s', 3)].count(&'s'), 4);
+//! assert_eq!(HashHistogram::par_merge(shards), h);
+//! ```
+//!
+//! Keys are hashed with `std::collections::hash_map::DefaultHasher`, so shard assignments are
+//! the same from run to run, but may change between Rust releases.
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+impl<T: KeyType + Send + Sync, C: CounterType + Send> HashHistogram<T, C> {
+    /// Builds a histogram of `items`, counting a contiguous chunk of the slice on each thread.
+    pub fn par_from_slice(items: &[T]) -> Self {
+        let chunk_size = items.len().div_ceil(threads()).max(1);
+        std::thread::scope(|s| {
+            let handles: Vec<_> = items
+                .chunks(chunk_size)
+                .map(|chunk| s.spawn(move || chunk.iter().collect::<Self>()))
+                .collect();
+            fold(handles.into_iter().map(|h| h.join().unwrap()))
+        })
+    }
+
+    /// Merges `histograms` by folding a group of them on each thread, then folding the results.
+    pub fn par_merge(histograms: Vec<Self>) -> Self {
+        let group_size = histograms.len().div_ceil(threads()).max(1);
+        let mut groups = Vec::new();
+        let mut remaining = histograms.into_iter().peekable();
+        while remaining.peek().is_some() {
+            groups.push(remaining.by_ref().take(group_size).collect::<Vec<_>>());
+        }
+        std::thread::scope(|s| {
+            let handles: Vec<_> = groups
+                .into_iter()
+                .map(|group| s.spawn(move || fold(group)))
+                .collect();
+            fold(handles.into_iter().map(|h| h.join().unwrap()))
+        })
+    }
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Splits the histogram into `n` histograms, placing each key in shard `shard_of(key, n)`.
+    /// Panics if `n` is zero.
+    pub fn shard_by(&self, n: usize) -> Vec<Self> {
+        assert!(n > 0, "number of shards must be positive");
+        let mut shards = vec![HashHistogram::new(); n];
+        for (key, count) in self.iter() {
+            shards[Self::shard_of(key, n)].bump_by(key, *count);
+        }
+        shards
+    }
+
+    /// The shard, from 0 to `n - 1`, that `shard_by(n)` places `item` in.
+    pub fn shard_of(item: &T, n: usize) -> usize {
+        let mut hasher = DefaultHasher::new();
+        item.hash(&mut hasher);
+        (hasher.finish() % n as u64) as usize
+    }
+}
+
+fn threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn fold<T: KeyType, C: CounterType, I: IntoIterator<Item = HashHistogram<T, C>>>(
+    histograms: I,
+) -> HashHistogram<T, C> {
+    let mut histograms = histograms.into_iter();
+    let mut result = histograms.next().unwrap_or_default();
+    for histogram in histograms {
+        for (key, count) in histogram.iter() {
+            result.bump_by(key, *count);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let words: Vec<String> = (0..5000).map(|i| format!("w{}", i % 37)).collect();
+        let sequential: HashHistogram<String> = words.iter().collect();
+        assert_eq!(HashHistogram::par_from_slice(&words), sequential);
+
+        let parts: Vec<HashHistogram<String>> = words
+            .chunks(300)
+            .map(|chunk| chunk.iter().collect())
+            .collect();
+        assert_eq!(HashHistogram::par_merge(parts), sequential);
+
+        assert_eq!(
+            HashHistogram::<String>::par_merge(vec![]),
+            HashHistogram::new()
+        );
+        assert_eq!(HashHistogram::<u8>::par_from_slice(&[]).len(), 0);
+    }
+
+    #[test]
+    fn test_shards_are_disjoint() {
+        let h: HashHistogram<u32> = (0..1000).map(|i| i % 101).collect();
+        let shards = h.shard_by(4);
+        assert_eq!(shards.iter().map(|s| s.len()).sum::<usize>(), 101);
+        assert!(shards.iter().all(|s| !s.is_empty()));
+        for (i, shard) in shards.iter().enumerate() {
+            for (key, count) in shard.iter() {
+                assert_eq!(HashHistogram::<u32>::shard_of(key, 4), i);
+                assert_eq!(*count, h.count(key));
+            }
+        }
+        assert_eq!(h.shard_by(1), vec![h.clone()]);
+    }
+}
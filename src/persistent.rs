@@ -0,0 +1,231 @@
+//! # Persistent histograms
+//! Available with the `persistent` feature.
+//!
+//! `PersistentHistogram` stores its counts in a hash trie whose nodes are shared between clones.
+//! Cloning takes constant time, and an update copies only the nodes on the path to the updated
+//! key, so a server can take a snapshot per request without copying a large map each time.
+//!
+//! ```
+//! use hash_histogram::PersistentHistogram;
+//!
+//! let mut live = PersistentHistogram::<u32>::new();
+//! for i in 0..10_000 {
+//!     live.bump(&(i % 100));
+//! }
+//!
+//! let snapshot = live.clone();
+//! live.bump(&7);
+//! assert_eq!(snapshot.count(&7), 100);
+//! assert_eq!(live.count(&7), 101);
+//! assert_eq!(snapshot.len(), 100);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+const BITS_PER_LEVEL: usize = 4;
+const BRANCHING: usize = 1 << BITS_PER_LEVEL;
+const MAX_DEPTH: usize = u64::BITS as usize / BITS_PER_LEVEL;
+const LEAF_CAPACITY: usize = 8;
+
+#[derive(Clone)]
+enum Node<T, C> {
+    /// Entries with their key hashes, which are kept for splitting the leaf.
+    Leaf(Vec<(u64, T, C)>),
+    Branch([Option<Arc<Node<T, C>>>; BRANCHING]),
+}
+
+#[derive(Clone)]
+pub struct PersistentHistogram<T: KeyType, C: CounterType = usize> {
+    root: Arc<Node<T, C>>,
+    len: usize,
+}
+
+impl<T: KeyType, C: CounterType> Default for PersistentHistogram<T, C> {
+    fn default() -> Self {
+        PersistentHistogram {
+            root: Arc::new(Node::Leaf(Vec::new())),
+            len: 0,
+        }
+    }
+}
+
+impl<T: KeyType, C: CounterType> PersistentHistogram<T, C> {
+    pub fn new() -> Self {
+        PersistentHistogram::default()
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, num::one());
+    }
+
+    pub fn bump_by(&mut self, item: &T, increment: C) {
+        if bump_node(&mut self.root, hash_of(item), 0, item, increment) {
+            self.len += 1;
+        }
+    }
+
+    pub fn count(&self, item: &T) -> C {
+        let hash = hash_of(item);
+        let mut node = self.root.as_ref();
+        let mut depth = 0;
+        loop {
+            match node {
+                Node::Leaf(entries) => {
+                    return entries
+                        .iter()
+                        .find(|(h, key, _)| *h == hash && key == item)
+                        .map_or(num::zero(), |(_, _, count)| *count);
+                }
+                Node::Branch(children) => match &children[slot(hash, depth)] {
+                    Some(child) => {
+                        node = child.as_ref();
+                        depth += 1;
+                    }
+                    None => return num::zero(),
+                },
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn total_count(&self) -> C {
+        self.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, C)> + '_ {
+        Iter {
+            stack: vec![self.root.as_ref()],
+            leaf: [].iter(),
+        }
+    }
+
+    pub fn to_histogram(&self) -> HashHistogram<T, C> {
+        self.iter()
+            .map(|(key, count)| (key.clone(), count))
+            .collect()
+    }
+}
+
+impl<T: KeyType, C: CounterType> From<&HashHistogram<T, C>> for PersistentHistogram<T, C> {
+    fn from(histogram: &HashHistogram<T, C>) -> Self {
+        let mut result = PersistentHistogram::new();
+        for (key, count) in histogram.iter() {
+            result.bump_by(key, *count);
+        }
+        result
+    }
+}
+
+struct Iter<'a, T, C> {
+    stack: Vec<&'a Node<T, C>>,
+    leaf: std::slice::Iter<'a, (u64, T, C)>,
+}
+
+impl<'a, T, C: Copy> Iterator for Iter<'a, T, C> {
+    type Item = (&'a T, C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((_, key, count)) = self.leaf.next() {
+                return Some((key, *count));
+            }
+            match self.stack.pop()? {
+                Node::Leaf(entries) => self.leaf = entries.iter(),
+                Node::Branch(children) => self
+                    .stack
+                    .extend(children.iter().flatten().map(|child| child.as_ref())),
+            }
+        }
+    }
+}
+
+fn hash_of<T: KeyType>(item: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn slot(hash: u64, depth: usize) -> usize {
+    (hash >> (depth * BITS_PER_LEVEL)) as usize & (BRANCHING - 1)
+}
+
+/// Adds `increment` to `item` below `node`, copying any shared nodes on the way. Returns `true`
+/// if `item` was not present before.
+fn bump_node<T: KeyType, C: CounterType>(
+    node: &mut Arc<Node<T, C>>,
+    hash: u64,
+    depth: usize,
+    item: &T,
+    increment: C,
+) -> bool {
+    match Arc::make_mut(node) {
+        Node::Leaf(entries) => {
+            if let Some((_, _, count)) = entries
+                .iter_mut()
+                .find(|(h, key, _)| *h == hash && key == item)
+            {
+                *count += increment;
+                return false;
+            }
+            entries.push((hash, item.clone(), increment));
+            if entries.len() > LEAF_CAPACITY && depth < MAX_DEPTH {
+                let mut children: [Option<Arc<Node<T, C>>>; BRANCHING] =
+                    std::array::from_fn(|_| None);
+                for entry in entries.drain(..) {
+                    let child = children[slot(entry.0, depth)]
+                        .get_or_insert_with(|| Arc::new(Node::Leaf(Vec::new())));
+                    if let Node::Leaf(child_entries) = Arc::make_mut(child) {
+                        child_entries.push(entry);
+                    }
+                }
+                *node = Arc::new(Node::Branch(children));
+            }
+            true
+        }
+        Node::Branch(children) => {
+            let child =
+                children[slot(hash, depth)].get_or_insert_with(|| Arc::new(Node::Leaf(Vec::new())));
+            bump_node(child, hash, depth + 1, item, increment)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_hash_histogram() {
+        let mut expected = HashHistogram::<u64, u32>::new();
+        let mut persistent = PersistentHistogram::<u64, u32>::new();
+        let mut snapshots = Vec::new();
+        for i in 0..5000u64 {
+            let key = i * i % 701;
+            expected.bump(&key);
+            persistent.bump(&key);
+            if i % 1000 == 0 {
+                snapshots.push((persistent.clone(), expected.clone()));
+            }
+        }
+        assert_eq!(persistent.len(), expected.len());
+        assert_eq!(persistent.to_histogram(), expected);
+        assert_eq!(persistent.total_count(), 5000);
+        for (snapshot, at_the_time) in snapshots {
+            assert_eq!(snapshot.to_histogram(), at_the_time);
+        }
+        assert_eq!(
+            PersistentHistogram::from(&expected).to_histogram(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! # Privacy
+//! `dp_release()` produces a differentially private copy of a histogram, suitable for sharing
+//! aggregate counts over user data. Each count receives two-sided geometric noise (the discrete
+//! analogue of Laplace noise) calibrated to `epsilon`, assuming that each user contributes at most
+//! one bump. Noisy counts are clamped at zero.
+//!
+//! Releasing a key at all reveals that someone bumped it. `dp_release_with_threshold()` also
+//! suppresses keys whose noisy counts do not exceed a threshold, the usual way of protecting the
+//! set of keys along with their counts.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let mut visits = HashHistogram::<&str>::new();
+//! visits.bump_by(&"home", 5000);
+//! visits.bump_by(&"rare page", 1);
+//!
+//! // A fixed sequence stands in for a random number generator here.
+//! let mut state = 0.0;
+//! let mut uniform = || {
+//!     state = (state + 0.618034) % 1.0;
+//!     state
+//! };
+//! let released = visits.dp_release_with_threshold(1.0, 20, &mut uniform);
+//! assert!(released.count(&"home").abs_diff(5000) < 50);
+//! assert_eq!(released.count(&"rare page"), 0);
+//! ```
+//!
+//! Frequency tables are often published only after removing keys observed fewer than `k` times,
+//! in the spirit of k-anonymity. `suppress_below()` does so and reports what was removed:
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let diagnoses: HashHistogram<&str> =
+//!     ["flu", "flu", "flu", "cold", "cold", "cold", "rare"].iter().collect();
+//! let (published, report) = diagnoses.suppress_below(3);
+//! assert_eq!(published.len(), 2);
+//! assert_eq!(report.keys_suppressed, 1);
+//! assert_eq!(report.observations_suppressed, 1);
+//! ```
+//!
+//! To count sensitive identifiers without retaining them, `with_key_hashing()` creates a
+//! `HashedHistogram`, which stores only salted 64-bit hashes of its keys. Counts can still be
+//! looked up by key, and rankings are reported by hash. Serializing `hashes()` leaves out both
+//! the keys and the salt.
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let mut logins = HashHistogram::<String>::with_key_hashing(0x5eed);
+//! for user in ["alice@example.com", "bob@example.com", "alice@example.com"].iter() {
+//!     logins.bump(&user.to_string());
+//! }
+//! assert_eq!(logins.count(&"alice@example.com".to_string()), 2);
+//! let top_hash = logins.ranking_with_counts()[0].0;
+//! assert_eq!(top_hash, logins.key_hash(&"alice@example.com".to_string()));
+//! # #[cfg(feature = "serde")] {
+//! let serialized = serde_json::to_string(logins.hashes()).unwrap();
+//! assert!(!serialized.contains("alice"));
+//! # }
+//! ```
+//!
+//! For attaching histogram dumps to bug reports, `redacted()` and `truncated_keys()` replace
+//! each key with its hash or with a prefix of its text, leaving the original histogram intact for
+//! local debugging. `serialize_redacted()` applies the hashing within a `serde` derive. Hashes
+//! hide keys from casual inspection, but a guessed key can be confirmed by hashing it.
+//! ```
+//! # #[cfg(feature = "serde")] {
+//! use hash_histogram::HashHistogram;
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Report {
+//!     #[serde(serialize_with = "hash_histogram::serialize_redacted")]
+//!     users: HashHistogram<String>,
+//! }
+//!
+//! let users: HashHistogram<String> = ["carol", "dave", "carol"]
+//!     .iter()
+//!     .map(|s| s.to_string())
+//!     .collect();
+//! let shortened = users.truncated_keys(2);
+//! assert_eq!(shortened.count(&"ca".to_string()), 2);
+//!
+//! let json = serde_json::to_string(&Report { users }).unwrap();
+//! assert!(!json.contains("carol"));
+//! # }
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Display;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+
+/// What `suppress_below()` removed from a histogram.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub struct SuppressionReport<C: CounterType = usize> {
+    pub keys_suppressed: usize,
+    pub observations_suppressed: C,
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Returns a copy of the histogram with two-sided geometric noise of privacy parameter
+    /// `epsilon` added to each count. Keys whose noisy counts fall to zero are omitted. `rng`
+    /// must return uniform samples from `[0, 1)`. Panics unless `epsilon` is positive.
+    pub fn dp_release<R: FnMut() -> f64>(&self, epsilon: f64, rng: &mut R) -> Self {
+        self.dp_release_with_threshold(epsilon, C::default(), rng)
+    }
+
+    /// As `dp_release()`, but omitting every key whose noisy count is at most `threshold`. Noisy
+    /// counts too large for `C` are saturated at its maximum.
+    pub fn dp_release_with_threshold<R: FnMut() -> f64>(
+        &self,
+        epsilon: f64,
+        threshold: C,
+        rng: &mut R,
+    ) -> Self {
+        assert!(epsilon > 0.0, "epsilon must be positive");
+        let mut result = HashHistogram::new();
+        for (key, count) in self.iter() {
+            let noise = sample_geometric(epsilon, rng) - sample_geometric(epsilon, rng);
+            let noisy = count.to_i128().unwrap_or(i128::MAX).saturating_add(noise);
+            let noisy = num::cast::<i128, C>(noisy.max(0)).unwrap_or_else(C::max_value);
+            if noisy > threshold {
+                result.bump_by(key, noisy);
+            }
+        }
+        result
+    }
+
+    /// Returns a copy of the histogram without the keys counted fewer than `k` times, along
+    /// with a report of how many keys and observations were removed.
+    pub fn suppress_below(&self, k: C) -> (Self, SuppressionReport<C>) {
+        let mut kept = HashHistogram::new();
+        let mut report = SuppressionReport::default();
+        for (key, count) in self.iter() {
+            if *count < k {
+                report.keys_suppressed += 1;
+                report.observations_suppressed += *count;
+            } else {
+                kept.bump_by(key, *count);
+            }
+        }
+        (kept, report)
+    }
+}
+
+/// A histogram that stores salted hashes of its keys rather than the keys themselves. Hashes
+/// are computed with `std::collections::hash_map::DefaultHasher`, whose algorithm may change
+/// between Rust releases.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HashedHistogram<T: KeyType, C: CounterType = usize> {
+    salt: u64,
+    hashes: HashHistogram<u64, C>,
+    keys: PhantomData<fn(&T)>,
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    pub fn with_key_hashing(salt: u64) -> HashedHistogram<T, C> {
+        HashedHistogram::new(salt)
+    }
+}
+
+impl<T: KeyType, C: CounterType> HashedHistogram<T, C> {
+    pub fn new(salt: u64) -> Self {
+        Self::from_hashes(HashHistogram::new(), salt)
+    }
+
+    /// Resumes counting with previously saved `hashes()`, which must have been produced using
+    /// the same `salt`.
+    pub fn from_hashes(hashes: HashHistogram<u64, C>, salt: u64) -> Self {
+        HashedHistogram {
+            salt,
+            hashes,
+            keys: PhantomData,
+        }
+    }
+
+    pub fn key_hash(&self, item: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(self.salt);
+        item.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, num::one());
+    }
+
+    pub fn bump_by(&mut self, item: &T, increment: C) {
+        self.hashes.bump_by(&self.key_hash(item), increment);
+    }
+
+    pub fn count(&self, item: &T) -> C {
+        self.hashes.count(&self.key_hash(item))
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    pub fn total_count(&self) -> C {
+        self.hashes.total_count()
+    }
+
+    pub fn ranking_with_counts(&self) -> Vec<(u64, C)> {
+        self.hashes.ranking_with_counts()
+    }
+
+    /// The counts of the hashed keys, without the salt.
+    pub fn hashes(&self) -> &HashHistogram<u64, C> {
+        &self.hashes
+    }
+
+    pub fn into_hashes(self) -> HashHistogram<u64, C> {
+        self.hashes
+    }
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Returns a copy of the histogram keyed by the hexadecimal hash of each key.
+    pub fn redacted(&self) -> HashHistogram<String, C> {
+        let mut result = HashHistogram::new();
+        for (key, count) in self.iter() {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            result.bump_by(&format!("{:016x}", hasher.finish()), *count);
+        }
+        result
+    }
+
+    /// Returns a copy of the histogram keyed by the first `len` characters of each key's text.
+    /// Keys sharing a prefix have their counts combined.
+    pub fn truncated_keys(&self, len: usize) -> HashHistogram<String, C>
+    where
+        T: Display,
+    {
+        let mut result = HashHistogram::new();
+        for (key, count) in self.iter() {
+            result.bump_by(&key.to_string().chars().take(len).collect(), *count);
+        }
+        result
+    }
+}
+
+/// Serializes the `redacted()` form of a histogram. Intended for use as
+/// `#[serde(serialize_with = "hash_histogram::serialize_redacted")]`.
+#[cfg(feature = "serde")]
+pub fn serialize_redacted<T, C, S>(
+    histogram: &HashHistogram<T, C>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: KeyType,
+    C: CounterType + Serialize,
+    S: Serializer,
+{
+    histogram.redacted().serialize(serializer)
+}
+
+/// Number of failures before the first success, with success probability `1 - exp(-epsilon)`.
+fn sample_geometric<R: FnMut() -> f64>(epsilon: f64, rng: &mut R) -> i128 {
+    let u = 1.0 - rng();
+    (u.ln() / -epsilon).floor() as i128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::test_rng;
+
+    #[test]
+    fn test_noise_scale() {
+        let mut h = HashHistogram::<u32, u64>::new();
+        for key in 0..2000 {
+            h.bump_by(&key, 1000);
+        }
+        let mut rng = test_rng(3);
+        let released = h.dp_release(0.5, &mut rng);
+        assert_eq!(released.len(), 2000);
+        let errors: Vec<f64> = released.iter().map(|(_, c)| *c as f64 - 1000.0).collect();
+        let mean = errors.iter().sum::<f64>() / errors.len() as f64;
+        let variance = errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64;
+        // Two-sided geometric noise with alpha = exp(-0.5) has variance 2 alpha / (1 - alpha)^2.
+        let alpha = (-0.5f64).exp();
+        assert!(mean.abs() < 0.3);
+        assert!((variance / (2.0 * alpha / (1.0 - alpha).powi(2)) - 1.0).abs() < 0.15);
+    }
+
+    #[test]
+    fn test_threshold() {
+        let mut h = HashHistogram::<char>::new();
+        h.bump(&'a');
+        h.bump_by(&'b', 100);
+        let mut rng = test_rng(11);
+        let released = h.dp_release_with_threshold(2.0, 10, &mut rng);
+        assert_eq!(released.count(&'a'), 0);
+        assert!(released.count(&'b') > 90);
+    }
+
+    #[test]
+    fn test_saturation() {
+        let mut h = HashHistogram::<u8, u8>::new();
+        for key in 0..100 {
+            h.bump_by(&key, 254);
+        }
+        let mut rng = test_rng(5);
+        let released = h.dp_release(0.5, &mut rng);
+        assert_eq!(released.len(), 100);
+        assert!(released.iter().any(|(_, c)| *c == u8::MAX));
+    }
+
+    #[test]
+    fn test_suppress_below() {
+        let h: HashHistogram<u8, u32> = [1, 1, 2, 2, 2, 3, 4].iter().collect();
+        let (kept, report) = h.suppress_below(2);
+        assert_eq!(kept.ranking_with_counts(), vec![(2, 3), (1, 2)]);
+        assert_eq!(
+            report,
+            SuppressionReport {
+                keys_suppressed: 2,
+                observations_suppressed: 2
+            }
+        );
+        let (all, report) = h.suppress_below(0);
+        assert_eq!(all, h);
+        assert_eq!(report, SuppressionReport::default());
+    }
+
+    #[test]
+    fn test_key_hashing() {
+        let mut h1 = HashHistogram::<&str, u32>::with_key_hashing(1);
+        let mut h2 = HashHistogram::<&str, u32>::with_key_hashing(2);
+        for s in ["x", "y", "x"].iter() {
+            h1.bump(s);
+            h2.bump(s);
+        }
+        assert_eq!(h1.count(&"x"), 2);
+        assert_eq!(h1.total_count(), 3);
+        assert_eq!(h1.len(), 2);
+        assert_ne!(h1.key_hash(&"x"), h2.key_hash(&"x"));
+
+        let mut resumed = HashedHistogram::from_hashes(h1.clone().into_hashes(), 1);
+        resumed.bump_by(&"y", 4);
+        assert_eq!(resumed.count(&"y"), 5);
+        assert_eq!(resumed.ranking_with_counts()[0], (h1.key_hash(&"y"), 5));
+    }
+
+    #[test]
+    fn test_redaction() {
+        let h: HashHistogram<u32> = [12345, 12399, 777, 12345].iter().collect();
+        let redacted = h.redacted();
+        assert_eq!(redacted.len(), 3);
+        assert_eq!(redacted.total_count(), 4);
+        assert!(redacted.iter().all(|(k, _)| k.len() == 16));
+        assert_eq!(redacted.max_count(), Some(2));
+
+        let truncated = h.truncated_keys(3);
+        assert_eq!(truncated.count(&"123".to_string()), 3);
+        assert_eq!(truncated.count(&"777".to_string()), 1);
+    }
+}
@@ -0,0 +1,320 @@
+//! # Queries
+//! `query()` filters a histogram with a small expression language, for ad hoc investigations
+//! where writing a closure would mean recompiling. Matching entries are returned from highest to
+//! lowest count.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let mut log = HashHistogram::<&str>::new();
+//! log.bump_by(&"err_timeout", 12);
+//! log.bump_by(&"err_refused", 3);
+//! log.bump_by(&"ok", 40);
+//! let frequent_errors = log.query("count >= 10 && key startswith 'err_'").unwrap();
+//! assert_eq!(frequent_errors, vec![("err_timeout", 12)]);
+//! assert_eq!(log.query("!(key == \"ok\")").unwrap().len(), 2);
+//! ```
+//!
+//! A query compares `count` to a whole number with `==`, `!=`, `<`, `<=`, `>`, or `>=`, or
+//! compares `key`, in its `Display` form, to a quoted string with `==`, `!=`, `startswith`,
+//! `endswith`, or `contains`. Comparisons combine with `&&`, `||`, `!`, and parentheses, with
+//! `&&` binding more tightly than `||`. A parsed `Query` can be reused with `query_with()`.
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// A malformed query. `position` is the byte offset of the problem in the expression.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueryError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A parsed query expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    Count(Comparison, u128),
+    Key(KeyTest, String),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeyTest {
+    Equal,
+    NotEqual,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+impl Query {
+    pub fn parse(expr: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            tokens: tokenize(expr)?,
+            next: 0,
+            end: expr.len(),
+        };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.next) {
+            Some((position, _)) => Err(error(*position, "unexpected input")),
+            None => Ok(query),
+        }
+    }
+
+    /// Whether an entry with the given key text and count matches.
+    pub fn matches(&self, key: &str, count: u128) -> bool {
+        match self {
+            Query::Count(comparison, n) => match comparison {
+                Comparison::Equal => count == *n,
+                Comparison::NotEqual => count != *n,
+                Comparison::Less => count < *n,
+                Comparison::LessOrEqual => count <= *n,
+                Comparison::Greater => count > *n,
+                Comparison::GreaterOrEqual => count >= *n,
+            },
+            Query::Key(test, text) => match test {
+                KeyTest::Equal => key == text,
+                KeyTest::NotEqual => key != text,
+                KeyTest::StartsWith => key.starts_with(text.as_str()),
+                KeyTest::EndsWith => key.ends_with(text.as_str()),
+                KeyTest::Contains => key.contains(text.as_str()),
+            },
+            Query::Not(query) => !query.matches(key, count),
+            Query::And(a, b) => a.matches(key, count) && b.matches(key, count),
+            Query::Or(a, b) => a.matches(key, count) || b.matches(key, count),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(expr: &str) -> Result<Self, QueryError> {
+        Query::parse(expr)
+    }
+}
+
+impl<T: KeyType + Display, C: CounterType> HashHistogram<T, C> {
+    pub fn query(&self, expr: &str) -> Result<Vec<(T, C)>, QueryError> {
+        Ok(self.query_with(&Query::parse(expr)?))
+    }
+
+    pub fn query_with(&self, query: &Query) -> Vec<(T, C)> {
+        self.iter_sorted_by_count()
+            .filter(|(key, count)| query.matches(&key.to_string(), count.to_u128().unwrap()))
+            .map(|(key, count)| (key.clone(), count))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Number(u128),
+    Text(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 11] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")"];
+
+fn error(position: usize, message: &str) -> QueryError {
+    QueryError {
+        position,
+        message: message.to_string(),
+    }
+}
+
+fn tokenize(expr: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.char_indices().peekable();
+    while let Some(&(start, c)) = rest.peek() {
+        if c.is_whitespace() {
+            rest.next();
+        } else if c == '\'' || c == '"' {
+            rest.next();
+            let mut text = String::new();
+            loop {
+                match rest.next() {
+                    Some((_, d)) if d == c => break,
+                    Some((_, d)) => text.push(d),
+                    None => return Err(error(start, "unclosed string")),
+                }
+            }
+            tokens.push((start, Token::Text(text)));
+        } else if c.is_ascii_digit() || c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(&(_, d)) = rest.peek() {
+                if !(d.is_alphanumeric() || d == '_') {
+                    break;
+                }
+                word.push(d);
+                rest.next();
+            }
+            let token = if c.is_ascii_digit() {
+                Token::Number(word.parse().map_err(|_| error(start, "invalid number"))?)
+            } else {
+                Token::Word(word)
+            };
+            tokens.push((start, token));
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| expr[start..].starts_with(**symbol))
+                .ok_or_else(|| error(start, "unexpected character"))?;
+            for _ in 0..symbol.len() {
+                rest.next();
+            }
+            tokens.push((start, Token::Symbol(symbol)));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |(p, _)| *p)
+    }
+
+    fn take(&mut self) -> Result<Token, QueryError> {
+        let token = self.peek().cloned();
+        let token = token.ok_or_else(|| error(self.end, "unexpected end of query"))?;
+        self.next += 1;
+        Ok(token)
+    }
+
+    fn accept(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.and()?;
+        while self.accept("||") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.unary()?;
+        while self.accept("&&") {
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryError> {
+        if self.accept("!") {
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+        if self.accept("(") {
+            let query = self.or()?;
+            if !self.accept(")") {
+                return Err(error(self.position(), "expected `)`"));
+            }
+            return Ok(query);
+        }
+        let position = self.position();
+        match self.take()? {
+            Token::Word(field) if field == "count" => {
+                let position = self.position();
+                let comparison = match self.take()? {
+                    Token::Symbol("==") => Comparison::Equal,
+                    Token::Symbol("!=") => Comparison::NotEqual,
+                    Token::Symbol("<") => Comparison::Less,
+                    Token::Symbol("<=") => Comparison::LessOrEqual,
+                    Token::Symbol(">") => Comparison::Greater,
+                    Token::Symbol(">=") => Comparison::GreaterOrEqual,
+                    _ => return Err(error(position, "expected a comparison")),
+                };
+                let position = self.position();
+                match self.take()? {
+                    Token::Number(n) => Ok(Query::Count(comparison, n)),
+                    _ => Err(error(position, "expected a number")),
+                }
+            }
+            Token::Word(field) if field == "key" => {
+                let position = self.position();
+                let test = match self.take()? {
+                    Token::Symbol("==") => KeyTest::Equal,
+                    Token::Symbol("!=") => KeyTest::NotEqual,
+                    Token::Word(w) if w == "startswith" => KeyTest::StartsWith,
+                    Token::Word(w) if w == "endswith" => KeyTest::EndsWith,
+                    Token::Word(w) if w == "contains" => KeyTest::Contains,
+                    _ => return Err(error(position, "expected a key test")),
+                };
+                let position = self.position();
+                match self.take()? {
+                    Token::Text(text) => Ok(Query::Key(test, text)),
+                    _ => Err(error(position, "expected a quoted string")),
+                }
+            }
+            _ => Err(error(position, "expected `count` or `key`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence_and_errors() {
+        let h: HashHistogram<u32> = [1, 2, 2, 3, 3, 3, 10, 10, 10, 10].iter().collect();
+        let keys = |expr: &str| -> Vec<u32> {
+            h.query(expr).unwrap().into_iter().map(|(k, _)| k).collect()
+        };
+        assert_eq!(
+            keys("count < 2 || count > 3 && key endswith '0'"),
+            vec![10, 1]
+        );
+        assert_eq!(
+            keys("(count < 2 || count > 3) && key endswith '0'"),
+            vec![10]
+        );
+        assert_eq!(keys("!(count != 2)"), vec![2]);
+        assert_eq!(keys("key contains \"\""), vec![10, 3, 2, 1]);
+
+        let err = |expr: &str| h.query(expr).unwrap_err().to_string();
+        assert_eq!(err("count >= 'x'"), "at 9: expected a number");
+        assert_eq!(err("size > 3"), "at 0: expected `count` or `key`");
+        assert_eq!(err("(count > 1"), "at 10: expected `)`");
+        assert_eq!(err("key == 'a"), "at 7: unclosed string");
+        assert_eq!(err("count > 1 count"), "at 10: unexpected input");
+        assert_eq!(err("count >"), "at 7: unexpected end of query");
+    }
+}
@@ -0,0 +1,89 @@
+//! # Quotas
+//! `try_bump_within_quota()` increments a count only if the result stays within a per-key quota,
+//! so a histogram can track allocations or act as a simple rate limiter while keeping all of its
+//! reporting features.
+//!
+//! ```
+//! use hash_histogram::{HashHistogram, QuotaExceeded};
+//!
+//! let mut requests = HashHistogram::<&str>::new();
+//! assert_eq!(requests.try_bump_within_quota(&"alice", 2), Ok(1));
+//! assert_eq!(requests.try_bump_within_quota(&"alice", 2), Ok(2));
+//! assert_eq!(
+//!     requests.try_bump_within_quota(&"alice", 2),
+//!     Err(QuotaExceeded { count: 2, increment: 1, quota: 2 })
+//! );
+//! assert_eq!(requests.count(&"alice"), 2);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::fmt;
+
+/// A refused increment, with the count of the key at the time.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct QuotaExceeded<C: CounterType = usize> {
+    pub count: C,
+    pub increment: C,
+    pub quota: C,
+}
+
+impl<C: CounterType + fmt::Display> fmt::Display for QuotaExceeded<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "adding {} to count {} would exceed quota {}",
+            self.increment, self.count, self.quota
+        )
+    }
+}
+
+impl<C: CounterType + fmt::Debug + fmt::Display> std::error::Error for QuotaExceeded<C> {}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Increments the count of `item` unless the result would exceed `quota`. Returns the new
+    /// count.
+    pub fn try_bump_within_quota(&mut self, item: &T, quota: C) -> Result<C, QuotaExceeded<C>> {
+        self.try_bump_by_within_quota(item, num::one(), quota)
+    }
+
+    /// Adds `increment` to the count of `item` unless the result would exceed `quota`. Returns
+    /// the new count.
+    pub fn try_bump_by_within_quota(
+        &mut self,
+        item: &T,
+        increment: C,
+        quota: C,
+    ) -> Result<C, QuotaExceeded<C>> {
+        let count = self.count(item);
+        match count.checked_add(&increment) {
+            Some(updated) if updated <= quota => {
+                self.bump_by(item, increment);
+                Ok(updated)
+            }
+            _ => Err(QuotaExceeded {
+                count,
+                increment,
+                quota,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_increments_refused() {
+        let mut h = HashHistogram::<char, u8>::new();
+        assert_eq!(h.try_bump_by_within_quota(&'x', 7, 10), Ok(7));
+        let refused = h.try_bump_by_within_quota(&'x', 4, 10).unwrap_err();
+        assert_eq!(
+            refused.to_string(),
+            "adding 4 to count 7 would exceed quota 10"
+        );
+        assert_eq!(h.try_bump_by_within_quota(&'x', 3, 10), Ok(10));
+        assert!(h.try_bump_by_within_quota(&'y', 255, u8::MAX).is_ok());
+        assert!(h.try_bump_within_quota(&'y', u8::MAX).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! # Rate limiting
+//! `RateLimiterHistogram` gives each key a token bucket. A bucket holds up to `capacity` tokens
+//! and regains one token per `refill` interval. `check()` spends a token if one is available, and
+//! otherwise reports how long until the next one. Allowed and denied requests are counted in
+//! ordinary `HashHistogram`s, so the usual rankings and reports show who is being limited.
+//!
+//! ```
+//! use hash_histogram::{Decision, RateLimiterHistogram};
+//! use std::time::{Duration, Instant};
+//!
+//! let mut limiter = RateLimiterHistogram::new(2, Duration::from_secs(10));
+//! let start = Instant::now();
+//! assert_eq!(limiter.check_at(&"alice", start), Decision::Allowed { remaining: 1 });
+//! assert_eq!(limiter.check_at(&"alice", start), Decision::Allowed { remaining: 0 });
+//! assert_eq!(
+//!     limiter.check_at(&"alice", start + Duration::from_secs(4)),
+//!     Decision::Denied { retry_after: Duration::from_secs(6) }
+//! );
+//! assert!(limiter.check_at(&"alice", start + Duration::from_secs(10)).is_allowed());
+//! assert_eq!(limiter.allowed().count(&"alice"), 3);
+//! assert_eq!(limiter.denied().ranking(), vec!["alice"]);
+//! ```
+
+use crate::{HashHistogram, KeyType};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The outcome of `RateLimiterHistogram::check()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Decision {
+    /// A token was spent, leaving `remaining` whole tokens in the bucket.
+    Allowed { remaining: u64 },
+    /// The bucket is empty; a token will be available after `retry_after`.
+    Denied { retry_after: Duration },
+}
+
+impl Decision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Decision::Allowed { .. })
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimiterHistogram<T: KeyType> {
+    capacity: u64,
+    refill: Duration,
+    buckets: HashMap<T, Bucket>,
+    allowed: HashHistogram<T, u64>,
+    denied: HashHistogram<T, u64>,
+}
+
+impl<T: KeyType> RateLimiterHistogram<T> {
+    /// Each key may make up to `capacity` requests in a burst, regaining one every `refill`.
+    /// Panics if `capacity` or `refill` is zero.
+    pub fn new(capacity: u64, refill: Duration) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        assert!(!refill.is_zero(), "refill interval must be positive");
+        RateLimiterHistogram {
+            capacity,
+            refill,
+            buckets: HashMap::new(),
+            allowed: HashHistogram::new(),
+            denied: HashHistogram::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn refill(&self) -> Duration {
+        self.refill
+    }
+
+    pub fn check(&mut self, item: &T) -> Decision {
+        self.check_at(item, Instant::now())
+    }
+
+    /// Decides whether `item` may make a request at `now`, spending a token if so.
+    pub fn check_at(&mut self, item: &T, now: Instant) -> Decision {
+        let tokens = self.tokens_at(item, now);
+        if tokens >= 1.0 {
+            let tokens = tokens - 1.0;
+            self.buckets.insert(
+                item.clone(),
+                Bucket {
+                    tokens,
+                    updated: now,
+                },
+            );
+            self.allowed.bump(item);
+            Decision::Allowed {
+                remaining: tokens as u64,
+            }
+        } else {
+            self.denied.bump(item);
+            Decision::Denied {
+                retry_after: self.refill.mul_f64(1.0 - tokens),
+            }
+        }
+    }
+
+    /// The tokens available to `item` at `now`, including partially refilled ones.
+    pub fn tokens_at(&self, item: &T, now: Instant) -> f64 {
+        self.buckets
+            .get(item)
+            .map_or(self.capacity as f64, |bucket| self.refilled(bucket, now))
+    }
+
+    /// Requests allowed so far, per key.
+    pub fn allowed(&self) -> &HashHistogram<T, u64> {
+        &self.allowed
+    }
+
+    /// Requests denied so far, per key.
+    pub fn denied(&self) -> &HashHistogram<T, u64> {
+        &self.denied
+    }
+
+    /// The tokens each key has spent and not yet regained as of `now`, rounded up. Keys with
+    /// full buckets are omitted.
+    pub fn usage_at(&self, now: Instant) -> HashHistogram<T, u64> {
+        let mut usage = HashHistogram::new();
+        for key in self.buckets.keys() {
+            let used = (self.capacity as f64 - self.tokens_at(key, now)).ceil() as u64;
+            if used > 0 {
+                usage.bump_by(key, used);
+            }
+        }
+        usage
+    }
+
+    /// Forgets the buckets of keys that have fully refilled by `now`, bounding memory use. The
+    /// allowed and denied counts are kept.
+    pub fn prune_at(&mut self, now: Instant) {
+        let (refill, capacity) = (self.refill, self.capacity as f64);
+        self.buckets
+            .retain(|_, bucket| Self::refill_bucket(bucket, refill, now) < capacity);
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        Self::refill_bucket(bucket, self.refill, now).min(self.capacity as f64)
+    }
+
+    fn refill_bucket(bucket: &Bucket, refill: Duration, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(bucket.updated);
+        bucket.tokens + elapsed.as_secs_f64() / refill.as_secs_f64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_and_prune() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut limiter = RateLimiterHistogram::new(3, second);
+        for _ in 0..5 {
+            limiter.check_at(&'a', start);
+        }
+        limiter.check_at(&'b', start);
+        assert_eq!(limiter.denied().count(&'a'), 2);
+        let usage = limiter.usage_at(start + second / 2);
+        assert_eq!(usage.count(&'a'), 3);
+        assert_eq!(usage.count(&'b'), 1);
+        limiter.prune_at(start + second);
+        assert_eq!(limiter.usage_at(start + second).ranking(), vec!['a']);
+        assert_eq!(limiter.tokens_at(&'b', start), 3.0);
+    }
+}
@@ -0,0 +1,474 @@
+//! # Text rendering
+//! `render_bars()` draws a horizontal bar chart for quick inspection in a terminal. Keys are
+//! listed from highest to lowest count, with ties broken as in `Display`, and bars are scaled so
+//! that the highest count fills `width` characters.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let mut drinks = HashHistogram::<&str>::new();
+//! drinks.bump_by(&"coffee", 8);
+//! drinks.bump_by(&"tea", 4);
+//! drinks.bump(&"juice");
+//! assert_eq!(
+//!     drinks.render_bars(8),
+//!     "coffee | ######## 8\n\
+//!      tea    | ####     4\n\
+//!      juice  | #        1\n"
+//! );
+//! ```
+//!
+//! `display_with()` formats a histogram like `Display`, but with `DisplayOptions` choosing the
+//! order of the entries, the separator, whether to show percentages of the total count instead
+//! of counts, and how many entries to show. Like `Display`, it compares keys by their `Debug`
+//! format; `display_with_key_order()` compares them with `Ord` instead.
+//!
+//! ```
+//! use hash_histogram::{DisplayOptions, DisplayOrder, HashHistogram};
+//!
+//! let h: HashHistogram<char> = "abracadabra".chars().collect();
+//! assert_eq!(h.to_string(), "a:5; b:2; r:2; c:1; d:1; ");
+//!
+//! let options = DisplayOptions {
+//!     order: DisplayOrder::Key,
+//!     descending: false,
+//!     separator: ", ".to_string(),
+//!     ..DisplayOptions::default()
+//! };
+//! assert_eq!(h.display_with(&options).to_string(), "a:5, b:2, c:1, d:1, r:2, ");
+//!
+//! let options = DisplayOptions::default().percentages().top(2);
+//! assert_eq!(h.display_with(&options).to_string(), "a:45.5%; b:18.2%; ");
+//!
+//! let lengths: HashHistogram<usize> = [9, 10, 10].iter().collect();
+//! let by_key = DisplayOptions {
+//!     order: DisplayOrder::Key,
+//!     descending: false,
+//!     ..DisplayOptions::default()
+//! };
+//! assert_eq!(lengths.display_with(&by_key).to_string(), "10:2; 9:1; ");
+//! assert_eq!(lengths.display_with_key_order(&by_key).to_string(), "9:1; 10:2; ");
+//! ```
+//!
+//! `to_markdown_table()` and `to_html_table()` produce tables of keys, counts, and percentages
+//! of the total count, in the same order as `render_bars()`, for pasting into reports and
+//! issues. Characters with special meaning in the markup are escaped.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<&str> = ["yes", "no", "yes", "yes"].iter().collect();
+//! assert_eq!(
+//!     h.to_markdown_table(),
+//!     "| Key | Count | Percent |\n\
+//!      |---|---:|---:|\n\
+//!      | yes | 3 | 75.0% |\n\
+//!      | no | 1 | 25.0% |\n"
+//! );
+//! ```
+//!
+//! A `NumberFormat` makes large counts readable with thousands separators or SI suffixes, and
+//! sets the precision of percentages.
+//!
+//! ```
+//! use hash_histogram::{HashHistogram, NumberFormat};
+//!
+//! let mut hits = HashHistogram::<&str, u64>::new();
+//! hits.bump_by(&"/", 123_456_789);
+//! hits.bump_by(&"/about", 4_321);
+//! let separated = NumberFormat {
+//!     thousands_separator: Some(','),
+//!     ..NumberFormat::default()
+//! };
+//! assert_eq!(
+//!     hits.render_bars_with(4, &separated),
+//!     "/      | #### 123,456,789\n/about | #    4,321\n"
+//! );
+//! let abbreviated = NumberFormat {
+//!     si_suffixes: true,
+//!     percent_decimals: 3,
+//!     ..NumberFormat::default()
+//! };
+//! assert_eq!(
+//!     hits.to_markdown_table_with(&abbreviated).lines().nth(3),
+//!     Some("| /about | 4.3k | 0.003% |")
+//! );
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::cmp::Ordering;
+use std::fmt::{self, Debug, Display};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DisplayOrder {
+    /// By count, breaking ties by key.
+    Count,
+    Key,
+}
+
+/// Formatting used by `display_with()`. The default formats like `Display`. Percentages of an
+/// empty total are shown as 0%.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DisplayOptions {
+    pub order: DisplayOrder,
+    pub descending: bool,
+    /// Show each key's percentage of the total count instead of its count.
+    pub percentages: bool,
+    /// Written after each entry.
+    pub separator: String,
+    /// Show only the first `n` entries in the chosen order.
+    pub top_n: Option<usize>,
+    pub number_format: NumberFormat,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        DisplayOptions {
+            order: DisplayOrder::Count,
+            descending: true,
+            percentages: false,
+            separator: "; ".to_string(),
+            top_n: None,
+            number_format: NumberFormat::default(),
+        }
+    }
+}
+
+impl DisplayOptions {
+    pub fn ascending(mut self) -> Self {
+        self.descending = false;
+        self
+    }
+
+    pub fn percentages(mut self) -> Self {
+        self.percentages = true;
+        self
+    }
+
+    pub fn top(mut self, n: usize) -> Self {
+        self.top_n = Some(n);
+        self
+    }
+}
+
+/// Formatting of the counts and percentages in `display_with()`, `render_bars_with()`, and the
+/// tables. The default writes counts in full and percentages to one decimal place.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NumberFormat {
+    /// Placed between groups of three digits of a count written in full.
+    pub thousands_separator: Option<char>,
+    /// Abbreviate counts of 1000 or more with SI suffixes to one decimal place, as in `1.2M`.
+    pub si_suffixes: bool,
+    pub percent_decimals: usize,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            thousands_separator: None,
+            si_suffixes: false,
+            percent_decimals: 1,
+        }
+    }
+}
+
+const SI_SUFFIXES: [&str; 10] = ["k", "M", "G", "T", "P", "E", "Z", "Y", "R", "Q"];
+
+impl NumberFormat {
+    pub fn count<C: CounterType>(&self, count: C) -> String {
+        let count = count.to_u128().unwrap();
+        if self.si_suffixes && count >= 1000 {
+            let mut value = count as f64;
+            let mut suffix = 0;
+            while value >= 999.95 && suffix < SI_SUFFIXES.len() {
+                value /= 1000.0;
+                suffix += 1;
+            }
+            return format!("{:.1}{}", value, SI_SUFFIXES[suffix - 1]);
+        }
+        let digits = count.to_string();
+        match self.thousands_separator {
+            Some(separator) => {
+                let mut result = String::new();
+                for (i, digit) in digits.chars().enumerate() {
+                    if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                        result.push(separator);
+                    }
+                    result.push(digit);
+                }
+                result
+            }
+            None => digits,
+        }
+    }
+
+    /// Formats a percentage, given as a number from 0 to 100, followed by `%`.
+    pub fn percent(&self, percent: f64) -> String {
+        format!("{:.*}%", self.percent_decimals, percent)
+    }
+}
+
+/// Formats a histogram according to `DisplayOptions`. Created by `display_with()` and
+/// `display_with_key_order()`.
+pub struct DisplayWith<'a, T: KeyType, C: CounterType> {
+    histogram: &'a HashHistogram<T, C>,
+    options: &'a DisplayOptions,
+    /// Compares keys, or `None` to compare their `Debug` format.
+    key_order: Option<fn(&T, &T) -> Ordering>,
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    pub fn display_with<'a>(&'a self, options: &'a DisplayOptions) -> DisplayWith<'a, T, C> {
+        DisplayWith {
+            histogram: self,
+            options,
+            key_order: None,
+        }
+    }
+
+    /// As `display_with()`, but sorting keys, and breaking ties between counts, with `Ord`.
+    pub fn display_with_key_order<'a>(
+        &'a self,
+        options: &'a DisplayOptions,
+    ) -> DisplayWith<'a, T, C>
+    where
+        T: Ord,
+    {
+        DisplayWith {
+            histogram: self,
+            options,
+            key_order: Some(T::cmp),
+        }
+    }
+}
+
+impl<T: KeyType + Debug + Display, C: CounterType> Display for DisplayWith<'_, T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = self.options;
+        let mut entries: Vec<(&T, String, C)> = self
+            .histogram
+            .iter()
+            .map(|(k, c)| (k, format!("{:?}", k), *c))
+            .collect();
+        let by_key =
+            |(k1, d1, _): &(&T, String, C), (k2, d2, _): &(&T, String, C)| match self.key_order {
+                Some(compare) => compare(k1, k2),
+                None => d1.cmp(d2),
+            };
+        match options.order {
+            DisplayOrder::Count => entries.sort_by(|e1, e2| {
+                let by_count = if options.descending {
+                    e2.2.cmp(&e1.2)
+                } else {
+                    e1.2.cmp(&e2.2)
+                };
+                by_count.then_with(|| by_key(e1, e2))
+            }),
+            DisplayOrder::Key if options.descending => entries.sort_by(|e1, e2| by_key(e2, e1)),
+            DisplayOrder::Key => entries.sort_by(by_key),
+        }
+        let total = self.histogram.total_count().to_f64().unwrap();
+        let shown = options.top_n.unwrap_or(entries.len());
+        for (key, _, count) in entries.into_iter().take(shown) {
+            let value = if options.percentages {
+                options.number_format.percent(percent_of(count, total))
+            } else {
+                options.number_format.count(count)
+            };
+            write!(f, "{}:{}{}", key, value, options.separator)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: KeyType + Debug + Display, C: CounterType> HashHistogram<T, C> {
+    pub fn to_markdown_table(&self) -> String {
+        self.to_markdown_table_with(&NumberFormat::default())
+    }
+
+    pub fn to_markdown_table_with(&self, format: &NumberFormat) -> String {
+        let mut result = String::from("| Key | Count | Percent |\n|---|---:|---:|\n");
+        for (key, count, percent) in self.table_rows() {
+            let key = key.replace('\\', "\\\\").replace('|', "\\|");
+            result.push_str(&format!(
+                "| {} | {} | {} |\n",
+                key,
+                format.count(count),
+                format.percent(percent)
+            ));
+        }
+        result
+    }
+
+    pub fn to_html_table(&self) -> String {
+        self.to_html_table_with(&NumberFormat::default())
+    }
+
+    pub fn to_html_table_with(&self, format: &NumberFormat) -> String {
+        let mut result =
+            String::from("<table>\n<tr><th>Key</th><th>Count</th><th>Percent</th></tr>\n");
+        for (key, count, percent) in self.table_rows() {
+            result.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&key),
+                format.count(count),
+                format.percent(percent)
+            ));
+        }
+        result.push_str("</table>\n");
+        result
+    }
+
+    fn table_rows(&self) -> Vec<(String, C, f64)> {
+        let total = self.total_count().to_f64().unwrap();
+        self.display_order()
+            .into_iter()
+            .map(|(key, count)| (key.to_string(), count, percent_of(count, total)))
+            .collect()
+    }
+
+    /// Renders one line per key: the key, a bar of `#` characters, and the count. Bar lengths
+    /// are rounded, but a positive count always gets at least one `#`.
+    pub fn render_bars(&self, width: usize) -> String {
+        self.render_bars_with(width, &NumberFormat::default())
+    }
+
+    pub fn render_bars_with(&self, width: usize, format: &NumberFormat) -> String {
+        let entries: Vec<(String, C)> = self
+            .display_order()
+            .into_iter()
+            .map(|(key, count)| (key.to_string(), count))
+            .collect();
+        let key_width = entries
+            .iter()
+            .map(|(key, _)| key.chars().count())
+            .max()
+            .unwrap_or(0);
+        let max = entries
+            .first()
+            .map_or(0.0, |(_, count)| count.to_f64().unwrap());
+        let mut result = String::new();
+        for (key, count) in entries {
+            let count_f64 = count.to_f64().unwrap();
+            let mut bar = (count_f64 / max * width as f64).round() as usize;
+            if count_f64 > 0.0 {
+                bar = bar.max(1);
+            }
+            result.push_str(&format!(
+                "{:key_width$} | {:width$} {}\n",
+                key,
+                "#".repeat(bar),
+                format.count(count)
+            ));
+        }
+        result
+    }
+}
+
+/// `count` as a percentage of `total`, or 0 if `total` is zero.
+fn percent_of<C: CounterType>(count: C, total: f64) -> f64 {
+    if total > 0.0 {
+        100.0 * count.to_f64().unwrap() / total
+    } else {
+        0.0
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_scaling() {
+        let mut h = HashHistogram::<u32, u64>::new();
+        h.bump_by(&1, 1000);
+        h.bump(&22);
+        h.bump_by(&333, 0);
+        assert_eq!(
+            h.render_bars(4),
+            "1   | #### 1000\n22  | #    1\n333 |      0\n"
+        );
+        assert_eq!(HashHistogram::<u32>::new().render_bars(10), "");
+    }
+
+    #[test]
+    fn test_table_escaping() {
+        let mut h = HashHistogram::<&str>::new();
+        h.bump_by(&"<a|b>", 3);
+        h.bump(&"\"&\"");
+        assert_eq!(
+            h.to_markdown_table().lines().nth(2),
+            Some("| <a\\|b> | 3 | 75.0% |")
+        );
+        assert_eq!(
+            h.to_html_table(),
+            "<table>\n\
+             <tr><th>Key</th><th>Count</th><th>Percent</th></tr>\n\
+             <tr><td>&lt;a|b&gt;</td><td>3</td><td>75.0%</td></tr>\n\
+             <tr><td>&quot;&amp;&quot;</td><td>1</td><td>25.0%</td></tr>\n\
+             </table>\n"
+        );
+    }
+
+    #[test]
+    fn test_number_format() {
+        let si = NumberFormat {
+            si_suffixes: true,
+            ..NumberFormat::default()
+        };
+        assert_eq!(si.count(999u32), "999");
+        assert_eq!(si.count(1_000u32), "1.0k");
+        assert_eq!(si.count(999_960u32), "1.0M");
+        assert_eq!(si.count(u128::MAX), "340282366.9Q");
+        let spaced = NumberFormat {
+            thousands_separator: Some(' '),
+            percent_decimals: 0,
+            ..NumberFormat::default()
+        };
+        assert_eq!(spaced.count(100_000u32), "100 000");
+        assert_eq!(spaced.count(1_000_000u32), "1 000 000");
+        assert_eq!(spaced.percent(33.3), "33%");
+    }
+
+    #[test]
+    fn test_display_options() {
+        let h: HashHistogram<u32> = [3, 10, 10, 9, 9, 9].iter().collect();
+        assert_eq!(
+            h.display_with(&DisplayOptions::default()).to_string(),
+            h.to_string()
+        );
+        let ascending = DisplayOptions::default().ascending();
+        assert_eq!(h.display_with(&ascending).to_string(), "3:1; 10:2; 9:3; ");
+        let by_key = DisplayOptions {
+            order: DisplayOrder::Key,
+            descending: false,
+            ..DisplayOptions::default()
+        };
+        assert_eq!(h.display_with(&by_key).to_string(), "10:2; 3:1; 9:3; ");
+        assert_eq!(
+            h.display_with_key_order(&by_key).to_string(),
+            "3:1; 9:3; 10:2; "
+        );
+        let reversed = DisplayOptions {
+            order: DisplayOrder::Key,
+            separator: "\n".to_string(),
+            ..DisplayOptions::default()
+        };
+        assert_eq!(
+            h.display_with_key_order(&reversed.top(2)).to_string(),
+            "10:2\n9:3\n"
+        );
+
+        let labeled = HashHistogram::<u32>::with_labels([1]);
+        let percentages = DisplayOptions::default().percentages();
+        assert_eq!(labeled.display_with(&percentages).to_string(), "1:0.0%; ");
+        assert!(labeled.to_markdown_table().contains("| 1 | 0 | 0.0% |"));
+    }
+}
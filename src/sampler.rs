@@ -0,0 +1,140 @@
+//! # Weighted sampling
+//! `build_alias_table()` precomputes Walker's alias method from the counts, in time linear in
+//! the number of keys. The resulting `AliasSampler` then draws keys with probability
+//! proportional to their counts in constant time per draw, using a single uniform sample from
+//! `[0, 1)`.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let mut weights = HashHistogram::<&str>::new();
+//! weights.bump_by(&"common", 3);
+//! weights.bump(&"rare");
+//! let sampler = weights.build_alias_table();
+//! assert_eq!(sampler.len(), 2);
+//!
+//! let mut state = 1u64;
+//! let mut rng = || {
+//!     state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+//!     (state >> 11) as f64 / (1u64 << 53) as f64
+//! };
+//! let draws: HashHistogram<&str> = (0..10_000)
+//!     .map(|_| *sampler.sample(&mut rng).unwrap())
+//!     .collect();
+//! assert!(draws.count(&"common") > 2 * draws.count(&"rare"));
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+
+/// Draws keys with probability proportional to their counts when the table was built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasSampler<T> {
+    keys: Vec<T>,
+    /// The probability of keeping column `i` rather than taking its alias.
+    keep: Vec<f64>,
+    alias: Vec<usize>,
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// Keys with a count of zero are never drawn.
+    pub fn build_alias_table(&self) -> AliasSampler<T> {
+        let (keys, weights): (Vec<T>, Vec<f64>) = self
+            .iter()
+            .filter(|(_, count)| **count > num::zero())
+            .map(|(key, count)| (key.clone(), count.to_f64().unwrap()))
+            .unzip();
+        let n = keys.len();
+        let total: f64 = weights.iter().sum();
+        let mut keep: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let mut alias: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..n).partition(|&i| keep[i] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            alias[s] = l;
+            keep[l] -= 1.0 - keep[s];
+            if keep[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // Whatever remains is 1 up to rounding error.
+        for i in small.into_iter().chain(large) {
+            keep[i] = 1.0;
+        }
+        AliasSampler { keys, keep, alias }
+    }
+}
+
+impl<T> AliasSampler<T> {
+    /// The number of keys that can be drawn.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Draws a key. `rng` must return uniform samples from `[0, 1)`. Returns `None` if no key
+    /// had a positive count.
+    pub fn sample<R: FnMut() -> f64>(&self, rng: &mut R) -> Option<&T> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let scaled = rng() * self.keys.len() as f64;
+        let column = (scaled as usize).min(self.keys.len() - 1);
+        let within = scaled - column as f64;
+        let index = if within < self.keep[column] {
+            column
+        } else {
+            self.alias[column]
+        };
+        Some(&self.keys[index])
+    }
+
+    /// The probability that `sample()` draws each key.
+    pub fn probabilities(&self) -> Vec<(&T, f64)> {
+        let n = self.keys.len() as f64;
+        let mut result: Vec<(&T, f64)> = self
+            .keys
+            .iter()
+            .zip(&self.keep)
+            .map(|(key, keep)| (key, keep / n))
+            .collect();
+        for (column, keep) in self.keep.iter().enumerate() {
+            result[self.alias[column]].1 += (1.0 - keep) / n;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_matches_counts() {
+        let mut h = HashHistogram::<u32, u64>::new();
+        for key in 0..50 {
+            h.bump_by(&key, (key * key % 17) as u64);
+        }
+        let sampler = h.build_alias_table();
+        assert_eq!(sampler.len(), h.len() - 3);
+        let total = h.total_count() as f64;
+        for (key, p) in sampler.probabilities() {
+            assert!((p - h.count(key) as f64 / total).abs() < 1e-12);
+        }
+        let mut rng = crate::stats::test_rng(11);
+        let draws: HashHistogram<u32> = (0..100_000)
+            .map(|_| *sampler.sample(&mut rng).unwrap())
+            .collect();
+        assert_eq!(draws.count(&0), 0);
+        assert!((draws.count(&6) as f64 / 100_000.0 - 2.0 / total).abs() < 0.005);
+        assert_eq!(
+            HashHistogram::<u8>::new()
+                .build_alias_table()
+                .sample(&mut rng),
+            None
+        );
+    }
+}
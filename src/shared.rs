@@ -0,0 +1,93 @@
+//! # Copy-on-write snapshots
+//! `SharedHistogram` keeps its `HashHistogram` behind an `Arc`. `cow_snapshot()` hands out a
+//! read-only view in constant time, by sharing that `Arc`. The histogram is copied only when it
+//! is next modified while a snapshot is still alive, so a reporting thread can read a consistent
+//! view without ingestion pausing for a clone at snapshot time.
+//!
+//! ```
+//! use hash_histogram::SharedHistogram;
+//! use std::thread;
+//!
+//! let mut live = SharedHistogram::<&str>::new();
+//! live.bump(&"a");
+//! live.bump(&"b");
+//!
+//! let snapshot = live.cow_snapshot();
+//! let reporter = thread::spawn(move || snapshot.total_count());
+//! live.bump(&"a");
+//!
+//! assert_eq!(reporter.join().unwrap(), 2);
+//! assert_eq!(live.count(&"a"), 2);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Reading methods of `HashHistogram` are available through `Deref`.
+#[derive(Debug, Clone, Default)]
+pub struct SharedHistogram<T: KeyType, C: CounterType = usize> {
+    histogram: Arc<HashHistogram<T, C>>,
+}
+
+impl<T: KeyType, C: CounterType> SharedHistogram<T, C> {
+    pub fn new() -> Self {
+        SharedHistogram::default()
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.make_mut().bump(item);
+    }
+
+    pub fn bump_by(&mut self, item: &T, increment: C) {
+        self.make_mut().bump_by(item, increment);
+    }
+
+    /// Mutable access to the histogram, which is copied first if any snapshot still shares it.
+    pub fn make_mut(&mut self) -> &mut HashHistogram<T, C> {
+        Arc::make_mut(&mut self.histogram)
+    }
+
+    /// A read-only view of the current counts, unaffected by later updates.
+    pub fn cow_snapshot(&self) -> Arc<HashHistogram<T, C>> {
+        Arc::clone(&self.histogram)
+    }
+
+    pub fn into_histogram(self) -> HashHistogram<T, C> {
+        Arc::try_unwrap(self.histogram).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl<T: KeyType, C: CounterType> Deref for SharedHistogram<T, C> {
+    type Target = HashHistogram<T, C>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.histogram
+    }
+}
+
+impl<T: KeyType, C: CounterType> From<HashHistogram<T, C>> for SharedHistogram<T, C> {
+    fn from(histogram: HashHistogram<T, C>) -> Self {
+        SharedHistogram {
+            histogram: Arc::new(histogram),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_deferred_until_write() {
+        let mut live = SharedHistogram::from(HashHistogram::<u8, u16>::from_iter([1, 2, 2]));
+        let first = live.cow_snapshot();
+        let second = live.cow_snapshot();
+        assert!(Arc::ptr_eq(&first, &second));
+        live.bump(&1);
+        assert!(!Arc::ptr_eq(&first, &live.cow_snapshot()));
+        assert_eq!(first.count(&1), 1);
+        drop((first, second));
+        assert_eq!(live.into_histogram().count(&1), 2);
+    }
+}
@@ -0,0 +1,183 @@
+//! # Signed histograms
+//! `SignedHistogram` allows counts to go negative, for bookkeeping of differences such as
+//! "requests started minus requests finished" per key. Keys whose counts return to zero are
+//! removed.
+//!
+//! ```
+//! use hash_histogram::SignedHistogram;
+//!
+//! let mut in_flight = SignedHistogram::<&str>::new();
+//! in_flight.bump(&"/api");
+//! in_flight.bump(&"/api");
+//! in_flight.bump(&"/home");
+//! in_flight.decrement(&"/home");
+//! in_flight.decrement(&"/login");
+//!
+//! assert_eq!(in_flight.count(&"/api"), 2);
+//! assert_eq!(in_flight.count(&"/home"), 0);
+//! assert_eq!(in_flight.count(&"/login"), -1);
+//! assert_eq!(in_flight.len(), 2);
+//! assert_eq!(in_flight.total_count(), 1);
+//! ```
+//!
+//! `delta_since()` reports how a `HashHistogram` has changed since an earlier `snapshot()`, as
+//! metric exporters need for rates of change.
+//!
+//! ```
+//! use hash_histogram::{HashHistogram, SignedHistogram};
+//!
+//! let mut requests = HashHistogram::<&str>::new();
+//! requests.bump_by(&"/api", 10);
+//! let before = requests.snapshot();
+//! requests.bump_by(&"/api", 5);
+//! requests.bump(&"/home");
+//!
+//! let delta: SignedHistogram<&str> = requests.delta_since(&before);
+//! assert_eq!(delta.count(&"/api"), 5);
+//! assert_eq!(delta.count(&"/home"), 1);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType, SignedCounterType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SignedHistogram<T: KeyType, C: SignedCounterType = isize> {
+    histogram: HashMap<T, C>,
+}
+
+impl<T: KeyType, C: SignedCounterType> SignedHistogram<T, C> {
+    pub fn new() -> Self {
+        SignedHistogram::default()
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, num::one());
+    }
+
+    pub fn decrement(&mut self, item: &T) {
+        self.bump_by(item, -C::one());
+    }
+
+    /// Adds `delta`, which may be negative, to the count of `item`.
+    pub fn bump_by(&mut self, item: &T, delta: C) {
+        match self.histogram.get_mut(item) {
+            None => {
+                if delta != num::zero() {
+                    self.histogram.insert(item.clone(), delta);
+                }
+            }
+            Some(count) => {
+                *count += delta;
+                if *count == num::zero() {
+                    self.histogram.remove(item);
+                }
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.histogram.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    pub fn count(&self, item: &T) -> C {
+        *self.histogram.get(item).unwrap_or(&num::zero())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, C)> + '_ {
+        self.histogram.iter().map(|(key, count)| (key, *count))
+    }
+
+    /// The net sum of all counts.
+    pub fn total_count(&self) -> C {
+        self.histogram.values().copied().sum()
+    }
+
+    pub fn ranking(&self) -> Vec<T> {
+        self.ranking_with_counts()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Keys paired with their counts, from most positive to most negative.
+    pub fn ranking_with_counts(&self) -> Vec<(T, C)> {
+        let mut ranking: Vec<(T, C)> = self.iter().map(|(k, c)| (k.clone(), c)).collect();
+        ranking.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+        ranking
+    }
+
+    pub fn mode(&self) -> Option<T> {
+        self.iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(key, _)| key.clone())
+    }
+}
+
+impl<T: KeyType, C: CounterType> HashHistogram<T, C> {
+    /// A copy of the current counts, for later use with `delta_since()`.
+    pub fn snapshot(&self) -> Self {
+        self.clone()
+    }
+
+    /// The change in each count since `earlier`. Keys whose counts are unchanged are omitted.
+    /// Panics if a count does not fit in `D`.
+    pub fn delta_since<D: SignedCounterType>(&self, earlier: &Self) -> SignedHistogram<T, D> {
+        let signed =
+            |count: C| -> D { num::cast(count).expect("count does not fit in signed type") };
+        let mut delta = SignedHistogram::new();
+        for (key, count) in self.iter() {
+            delta.bump_by(key, signed(*count) - signed(earlier.count(key)));
+        }
+        for (key, count) in earlier.iter() {
+            if !self.contains(key) {
+                delta.bump_by(key, -signed(*count));
+            }
+        }
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_counts() {
+        let mut h = SignedHistogram::<char, i32>::new();
+        h.bump_by(&'a', 5);
+        h.bump_by(&'b', -3);
+        h.bump_by(&'c', 0);
+        assert_eq!(h.len(), 2);
+        assert_eq!(h.ranking_with_counts(), vec![('a', 5), ('b', -3)]);
+        h.bump_by(&'a', -7);
+        assert_eq!(h.ranking(), vec!['a', 'b']);
+        assert_eq!(h.mode(), Some('a'));
+        assert_eq!(h.total_count(), -5);
+        h.bump_by(&'b', 3);
+        assert_eq!(h.len(), 1);
+    }
+
+    #[test]
+    fn test_delta_since() {
+        let mut h: HashHistogram<char, u32> = "aab".chars().collect();
+        let before = h.snapshot();
+        h.bump(&'c');
+        h.scale_by(2);
+        let delta: SignedHistogram<char, i64> = h.delta_since(&before);
+        assert_eq!(delta.count(&'a'), 2);
+        assert_eq!(delta.count(&'c'), 2);
+        let reverse: SignedHistogram<char, i64> = before.delta_since(&h);
+        assert_eq!(reverse.count(&'c'), -2);
+        assert_eq!(h.delta_since::<i32>(&h).len(), 0);
+        let labeled = HashHistogram::<char, u32>::with_labels(['k']);
+        let earlier: HashHistogram<char, u32> = "kkk".chars().collect();
+        assert_eq!(labeled.delta_since::<i64>(&earlier).count(&'k'), -3);
+    }
+}
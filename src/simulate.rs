@@ -0,0 +1,154 @@
+//! # Replaying operations
+//! A `HistogramOp` records one update, and `simulate_ops()` replays a sequence of them on a new
+//! histogram. Every counting structure in the crate implements `ApplyOp`, so the same recorded
+//! sequence can be replayed on each, for differential testing of one implementation against
+//! another. With the `serde` feature, sequences can be saved and replayed later.
+//!
+//! ```
+//! use hash_histogram::{simulate_ops, ApplyOp, ConcurrentHashHistogram, CountMinSketch};
+//! use hash_histogram::{HashHistogram, HistogramOp};
+//!
+//! let ops = vec![
+//!     HistogramOp::Bump("a"),
+//!     HistogramOp::BumpBy("b", 3),
+//!     HistogramOp::Bump("a"),
+//! ];
+//! let exact: HashHistogram<&str> = simulate_ops(&ops);
+//! let concurrent: ConcurrentHashHistogram<&str> = simulate_ops(&ops);
+//! assert_eq!(concurrent.snapshot(), exact);
+//!
+//! let mut sketch = CountMinSketch::new(16, 2);
+//! sketch.apply_ops(&ops);
+//! assert!(sketch.count(&"b") >= exact.count(&"b"));
+//! ```
+
+use crate::{
+    ConcurrentHashHistogram, CountMinSketch, CounterType, HashHistogram, HeavyHitters,
+    HybridHistogram, KeyType, SharedHistogram,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// One update to a histogram.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HistogramOp<T, C = usize> {
+    Bump(T),
+    BumpBy(T, C),
+}
+
+/// A histogram that can replay a `HistogramOp`.
+pub trait ApplyOp<T, C> {
+    fn apply_op(&mut self, op: &HistogramOp<T, C>);
+
+    fn apply_ops(&mut self, ops: &[HistogramOp<T, C>]) {
+        for op in ops {
+            self.apply_op(op);
+        }
+    }
+}
+
+/// Replays `ops`, in order, on a new histogram.
+pub fn simulate_ops<H: ApplyOp<T, C> + Default, T, C>(ops: &[HistogramOp<T, C>]) -> H {
+    let mut result = H::default();
+    result.apply_ops(ops);
+    result
+}
+
+impl<T: KeyType, C: CounterType> ApplyOp<T, C> for HashHistogram<T, C> {
+    fn apply_op(&mut self, op: &HistogramOp<T, C>) {
+        match op {
+            HistogramOp::Bump(item) => self.bump(item),
+            HistogramOp::BumpBy(item, increment) => self.bump_by(item, *increment),
+        }
+    }
+}
+
+impl<T: KeyType, C: CounterType> ApplyOp<T, C> for SharedHistogram<T, C> {
+    fn apply_op(&mut self, op: &HistogramOp<T, C>) {
+        self.make_mut().apply_op(op);
+    }
+}
+
+impl<T: KeyType> ApplyOp<T, usize> for ConcurrentHashHistogram<T> {
+    fn apply_op(&mut self, op: &HistogramOp<T, usize>) {
+        match op {
+            HistogramOp::Bump(item) => self.bump(item),
+            HistogramOp::BumpBy(item, increment) => self.bump_by(item, *increment),
+        }
+    }
+}
+
+impl<T: KeyType, C: CounterType> ApplyOp<T, C> for CountMinSketch<T, C> {
+    fn apply_op(&mut self, op: &HistogramOp<T, C>) {
+        match op {
+            HistogramOp::Bump(item) => self.bump(item),
+            HistogramOp::BumpBy(item, increment) => self.bump_by(item, *increment),
+        }
+    }
+}
+
+impl<T: KeyType, C: CounterType> ApplyOp<T, C> for HeavyHitters<T, C> {
+    fn apply_op(&mut self, op: &HistogramOp<T, C>) {
+        match op {
+            HistogramOp::Bump(item) => self.bump(item),
+            HistogramOp::BumpBy(item, increment) => self.bump_by(item, *increment),
+        }
+    }
+}
+
+impl<T: KeyType, C: CounterType> ApplyOp<T, C> for HybridHistogram<T, C> {
+    fn apply_op(&mut self, op: &HistogramOp<T, C>) {
+        match op {
+            HistogramOp::Bump(item) => self.bump(item),
+            HistogramOp::BumpBy(item, increment) => self.bump_by(item, *increment),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backends_agree() {
+        let mut rng = crate::stats::test_rng(5);
+        let ops: Vec<HistogramOp<u8>> = (0..2000)
+            .map(|_| {
+                let key = (rng() * 40.0) as u8;
+                if rng() < 0.5 {
+                    HistogramOp::Bump(key)
+                } else {
+                    HistogramOp::BumpBy(key, (rng() * 5.0) as usize)
+                }
+            })
+            .collect();
+        let exact: HashHistogram<u8> = simulate_ops(&ops);
+        let shared: SharedHistogram<u8> = simulate_ops(&ops);
+        let concurrent: ConcurrentHashHistogram<u8> = simulate_ops(&ops);
+        assert_eq!(*shared, exact);
+        assert_eq!(concurrent.snapshot(), exact);
+
+        let mut sketch = CountMinSketch::new(8, 3);
+        let mut hybrid = HybridHistogram::new(10, 0.1, 0.01);
+        let mut heavy = HeavyHitters::new(10);
+        sketch.apply_ops(&ops);
+        hybrid.apply_ops(&ops);
+        heavy.apply_ops(&ops);
+        for (key, count) in exact.iter() {
+            assert!(sketch.count(key) >= *count);
+            assert!(hybrid.count(key) >= *count);
+            assert!(heavy.guaranteed_count(key) <= *count);
+        }
+        assert_eq!(sketch.total_count(), exact.total_count());
+        assert_eq!(hybrid.total_count(), exact.total_count());
+        assert_eq!(heavy.total_count(), exact.total_count());
+
+        #[cfg(feature = "serde")]
+        {
+            let saved = serde_json::to_string(&ops).unwrap();
+            let replayed: Vec<HistogramOp<u8>> = serde_json::from_str(&saved).unwrap();
+            assert_eq!(simulate_ops::<HashHistogram<u8>, _, _>(&replayed), exact);
+        }
+    }
+}
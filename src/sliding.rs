@@ -0,0 +1,159 @@
+//! # Sliding windows
+//! `SlidingWindowHistogram` counts only recent observations: those made within a time window,
+//! or the most recent `n` observations. Older observations expire whenever a new one is
+//! recorded. When observations may stop arriving, call `expire()` before querying so that stale
+//! counts are dropped.
+//!
+//! ```
+//! use hash_histogram::SlidingWindowHistogram;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut errors = SlidingWindowHistogram::with_duration(Duration::from_secs(300));
+//! let start = Instant::now();
+//! errors.bump_at(&"timeout", start);
+//! errors.bump_at(&"timeout", start + Duration::from_secs(10));
+//! errors.bump_at(&"refused", start + Duration::from_secs(200));
+//! errors.bump_at(&"refused", start + Duration::from_secs(305));
+//! assert_eq!(errors.count(&"timeout"), 1);
+//! assert_eq!(errors.mode(), Some("refused"));
+//!
+//! let mut recent = SlidingWindowHistogram::with_observations(3);
+//! for status in [200, 500, 200, 404, 404] {
+//!     recent.bump(&status);
+//! }
+//! assert_eq!(recent.ranking_with_counts(), vec![(404, 2), (200, 1)]);
+//! ```
+
+use crate::{HashHistogram, KeyType};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Which observations a `SlidingWindowHistogram` retains.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Window {
+    /// Observations made less than this long ago.
+    Time(Duration),
+    /// This many of the most recent observations.
+    Observations(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct SlidingWindowHistogram<T: KeyType> {
+    window: Window,
+    events: VecDeque<(Instant, T)>,
+    counts: HashHistogram<T>,
+}
+
+impl<T: KeyType> SlidingWindowHistogram<T> {
+    pub fn new(window: Window) -> Self {
+        SlidingWindowHistogram {
+            window,
+            events: VecDeque::new(),
+            counts: HashHistogram::new(),
+        }
+    }
+
+    pub fn with_duration(duration: Duration) -> Self {
+        Self::new(Window::Time(duration))
+    }
+
+    /// Retains the most recent `observations` observations.
+    pub fn with_observations(observations: usize) -> Self {
+        Self::new(Window::Observations(observations))
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_at(item, Instant::now());
+    }
+
+    /// Records `item` as observed at `time`. Times must not decrease from one call to the next.
+    pub fn bump_at(&mut self, item: &T, time: Instant) {
+        self.events.push_back((time, item.clone()));
+        self.counts.bump(item);
+        self.expire_at(time);
+    }
+
+    pub fn expire(&mut self) {
+        self.expire_at(Instant::now());
+    }
+
+    /// Drops the observations that fall outside the window as of `now`.
+    pub fn expire_at(&mut self, now: Instant) {
+        while let Some((time, _)) = self.events.front() {
+            let expired = match self.window {
+                Window::Time(duration) => now.saturating_duration_since(*time) >= duration,
+                Window::Observations(limit) => self.events.len() > limit,
+            };
+            if !expired {
+                break;
+            }
+            let (_, item) = self.events.pop_front().unwrap();
+            if let Some(count) = self.counts.histogram.get_mut(&item) {
+                *count -= 1;
+                self.counts.total = self.counts.total.map(|total| total - 1);
+                if *count == 0 {
+                    self.counts.histogram.remove(&item);
+                }
+            }
+        }
+    }
+
+    pub fn count(&self, item: &T) -> usize {
+        self.counts.count(item)
+    }
+
+    /// The number of distinct keys within the window.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn total_count(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn ranking(&self) -> Vec<T> {
+        self.counts.ranking()
+    }
+
+    pub fn ranking_with_counts(&self) -> Vec<(T, usize)> {
+        self.counts.ranking_with_counts()
+    }
+
+    pub fn mode(&self) -> Option<T> {
+        self.counts.mode()
+    }
+
+    /// The counts within the window.
+    pub fn histogram(&self) -> &HashHistogram<T> {
+        &self.counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_window_expiry() {
+        let start = Instant::now();
+        let mut h = SlidingWindowHistogram::with_duration(Duration::from_secs(60));
+        for second in 0..120 {
+            h.bump_at(&(second % 3), start + Duration::from_secs(second));
+        }
+        assert_eq!(h.total_count(), 60);
+        assert_eq!(h.count(&0), 20);
+        h.expire_at(start + Duration::from_secs(150));
+        assert_eq!(h.total_count(), 29);
+        h.expire_at(start + Duration::from_secs(500));
+        assert_eq!(h.len(), 0);
+        assert_eq!(h.mode(), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! # Good–Turing smoothing
+//! Raw frequencies give no probability to events that have not yet been observed, and they are
+//! unreliable for rare events. Good–Turing estimation reserves part of the probability mass for
+//! unseen keys, based on how many keys were seen exactly once, and adjusts the probabilities of
+//! observed keys using the frequency of frequencies from `counts_of_counts()`.
+//!
+//! This module implements the Simple Good–Turing method of Gale and Sampson, which smooths the
+//! frequency of frequencies with a log-linear fit before applying Turing's formula.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let h: HashHistogram<&str> = ["a", "a", "a", "b", "b", "c", "d"].iter().collect();
+//!
+//! // Two of the seven observations were of keys seen once.
+//! assert_eq!(h.probability_of_unseen(), 2.0 / 7.0);
+//!
+//! let seen: f64 = ["a", "b", "c", "d"].iter().map(|k| h.good_turing_estimate(k)).sum();
+//! assert!((seen + h.probability_of_unseen() - 1.0).abs() < 1e-12);
+//! assert!(h.good_turing_estimate(&"c") < 1.0 / 7.0);
+//!
+//! // Estimating every key at once builds the smoothed table only once.
+//! let estimates = h.good_turing_estimates();
+//! assert_eq!(estimates.weight(&"c"), h.good_turing_estimate(&"c"));
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType, WeightedHistogram};
+use std::collections::BTreeMap;
+use std::hash::Hash;
+
+impl<T: KeyType, C: CounterType + Hash> HashHistogram<T, C> {
+    /// The smoothed probability of `item`. Unseen keys get 0.0; the probability mass shared by
+    /// all of them is given by `probability_of_unseen()`. Each call smooths the whole histogram,
+    /// so use `good_turing_estimates()` to estimate many keys.
+    pub fn good_turing_estimate(&self, item: &T) -> f64 {
+        let count = self.count(item);
+        if count == num::zero() {
+            return 0.0;
+        }
+        let count = count.to_u128().unwrap();
+        self.good_turing_probabilities()
+            .get(&count)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// The smoothed probability of every key with a positive count.
+    pub fn good_turing_estimates(&self) -> WeightedHistogram<T> {
+        let probabilities = self.good_turing_probabilities();
+        let mut estimates = WeightedHistogram::new();
+        for (key, count) in self.iter().filter(|(_, c)| **c > num::zero()) {
+            estimates.bump_by(key, probabilities[&count.to_u128().unwrap()]);
+        }
+        estimates
+    }
+
+    /// The estimated probability that the next observation is of a key not yet seen: the
+    /// proportion of observations that were of keys seen exactly once.
+    pub fn probability_of_unseen(&self) -> f64 {
+        let total = self.total_count().to_f64().unwrap();
+        if total == 0.0 {
+            return 1.0;
+        }
+        self.counts_of_counts().count(&num::one()) as f64 / total
+    }
+
+    /// Maps each observed count to the smoothed probability of a single key with that count.
+    /// Keys with a count of zero, such as those from `with_labels()`, were never observed.
+    pub fn good_turing_probabilities(&self) -> BTreeMap<u128, f64> {
+        let frequencies: BTreeMap<u128, f64> = self
+            .counts_of_counts()
+            .iter()
+            .filter(|(count, _)| **count > num::zero())
+            .map(|(count, keys)| (count.to_u128().unwrap(), *keys as f64))
+            .collect();
+        let counts: Vec<u128> = frequencies.keys().copied().collect();
+        let slope = log_linear_slope(&frequencies);
+
+        let mut adjusted = BTreeMap::new();
+        let mut use_fit = counts.len() < 2;
+        for &r in counts.iter() {
+            let rf = r as f64;
+            let fit_estimate = match slope {
+                Some(b) => (rf + 1.0) * ((rf + 1.0) / rf).powf(b),
+                None => rf,
+            };
+            let r_star = match frequencies.get(&(r + 1)) {
+                Some(next) if !use_fit => {
+                    let n_r = frequencies[&r];
+                    let turing = (rf + 1.0) * next / n_r;
+                    let deviation = 1.96
+                        * ((rf + 1.0).powi(2) * next / n_r.powi(2) * (1.0 + next / n_r)).sqrt();
+                    if (turing - fit_estimate).abs() <= deviation {
+                        use_fit = true;
+                        fit_estimate
+                    } else {
+                        turing
+                    }
+                }
+                _ => {
+                    use_fit = true;
+                    fit_estimate
+                }
+            };
+            adjusted.insert(r, r_star);
+        }
+
+        let unnormalized: f64 = adjusted
+            .iter()
+            .map(|(r, r_star)| frequencies[r] * r_star)
+            .sum();
+        let seen_mass = 1.0 - self.probability_of_unseen();
+        adjusted
+            .into_iter()
+            .map(|(r, r_star)| (r, seen_mass * r_star / unnormalized))
+            .collect()
+    }
+}
+
+/// The slope `b` of the least-squares fit `log(Z_r) = a + b log(r)`, where `Z_r` averages the
+/// number of keys with count `r` over the gap to the neighboring observed counts. Only the slope
+/// is needed, since the intercept cancels out of the smoothed Turing formula. Returns `None` with
+/// fewer than two distinct counts.
+fn log_linear_slope(frequencies: &BTreeMap<u128, f64>) -> Option<f64> {
+    if frequencies.len() < 2 {
+        return None;
+    }
+    let counts: Vec<u128> = frequencies.keys().copied().collect();
+    let points: Vec<(f64, f64)> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, &r)| {
+            let previous = if i == 0 { 0 } else { counts[i - 1] };
+            let next = counts.get(i + 1).copied().unwrap_or(2 * r - previous);
+            let z = 2.0 * frequencies[&r] / (next - previous) as f64;
+            ((r as f64).ln(), z.ln())
+        })
+        .collect();
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    Some(covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_good_turing() {
+        let mut h = HashHistogram::<u32, u32>::new();
+        for key in 0..120 {
+            h.bump(&key);
+        }
+        for key in 120..160 {
+            h.bump_by(&key, 2);
+        }
+        for key in 160..180 {
+            h.bump_by(&key, 3);
+        }
+        for key in 180..185 {
+            h.bump_by(&key, 10);
+        }
+        h.bump_by(&185, 100);
+
+        let total = h.total_count() as f64;
+        assert_eq!(h.probability_of_unseen(), 120.0 / total);
+        let mass: f64 = (0..186).map(|k| h.good_turing_estimate(&k)).sum();
+        assert!((mass + h.probability_of_unseen() - 1.0).abs() < 1e-9);
+        assert!(h.good_turing_estimate(&0) < 1.0 / total);
+        assert!(h.good_turing_estimate(&0) < h.good_turing_estimate(&120));
+        assert!(h.good_turing_estimate(&185) > h.good_turing_estimate(&180));
+        assert_eq!(h.good_turing_estimate(&1000), 0.0);
+
+        let estimates = h.good_turing_estimates();
+        assert_eq!(estimates.len(), 186);
+        assert_eq!(estimates.weight(&185), h.good_turing_estimate(&185));
+        let mut labeled = HashHistogram::with_labels([999]);
+        for (key, count) in h.iter() {
+            labeled.bump_by(key, *count);
+        }
+        assert_eq!(labeled.good_turing_estimate(&0), h.good_turing_estimate(&0));
+        assert_eq!(labeled.good_turing_estimate(&999), 0.0);
+        assert_eq!(labeled.good_turing_estimates(), estimates);
+        assert_eq!(
+            HashHistogram::<u32, u32>::new().probability_of_unseen(),
+            1.0
+        );
+    }
+}
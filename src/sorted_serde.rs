@@ -0,0 +1,91 @@
+//! # Deterministic serialization
+//! The derived `serde` implementation of `HashHistogram` writes its entries in the arbitrary
+//! order of the underlying `HashMap`, so equal histograms can serialize differently. For
+//! snapshot tests and content-addressed storage, `serialize_sorted_by_key()` and
+//! `serialize_sorted_by_count()` write the entries as a list of `[key, count]` pairs in a fixed
+//! order, and `deserialize_entries()` reads such a list back.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Snapshot {
+//!     #[serde(
+//!         serialize_with = "hash_histogram::serialize_sorted_by_key",
+//!         deserialize_with = "hash_histogram::deserialize_entries"
+//!     )]
+//!     words: HashHistogram<String>,
+//! }
+//!
+//! let words = ["b", "a", "c", "a"].iter().map(|s| s.to_string()).collect();
+//! let snapshot = Snapshot { words };
+//! let json = serde_json::to_string(&snapshot).unwrap();
+//! assert_eq!(json, r#"{"words":[["a",2],["b",1],["c",1]]}"#);
+//! assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes the entries as `[key, count]` pairs in key order. Intended for use as
+/// `#[serde(serialize_with = "hash_histogram::serialize_sorted_by_key")]`.
+pub fn serialize_sorted_by_key<T, C, S>(
+    histogram: &HashHistogram<T, C>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: KeyType + Ord + Serialize,
+    C: CounterType + Serialize,
+    S: Serializer,
+{
+    let mut entries: Vec<(&T, &C)> = histogram.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    entries.serialize(serializer)
+}
+
+/// Serializes the entries as `[key, count]` pairs from highest to lowest count, breaking ties by
+/// key order.
+pub fn serialize_sorted_by_count<T, C, S>(
+    histogram: &HashHistogram<T, C>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: KeyType + Ord + Serialize,
+    C: CounterType + Serialize,
+    S: Serializer,
+{
+    let mut entries: Vec<(&T, &C)> = histogram.iter().collect();
+    entries.sort_by(|(k1, c1), (k2, c2)| c2.cmp(c1).then_with(|| k1.cmp(k2)));
+    entries.serialize(serializer)
+}
+
+/// Reads a list of `[key, count]` pairs, as written by `serialize_sorted_by_key()` and
+/// `serialize_sorted_by_count()`. Counts for repeated keys are added together.
+pub fn deserialize_entries<'de, T, C, D>(deserializer: D) -> Result<HashHistogram<T, C>, D::Error>
+where
+    T: KeyType + Deserialize<'de>,
+    C: CounterType + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let entries: Vec<(T, C)> = Vec::deserialize(deserializer)?;
+    Ok(entries.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_by_count() {
+        let h: HashHistogram<char, u8> = "cabbcc".chars().collect();
+        let mut out = Vec::new();
+        serialize_sorted_by_count(&h, &mut serde_json::Serializer::new(&mut out)).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert_eq!(json, r#"[["c",3],["b",2],["a",1]]"#);
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        let parsed: HashHistogram<char, u8> = deserialize_entries(&mut deserializer).unwrap();
+        assert_eq!(parsed, h);
+    }
+}
@@ -0,0 +1,157 @@
+// Numerical routines shared by the statistical features of the crate.
+
+/// Natural logarithm of the gamma function, using the Lanczos approximation.
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let series: f64 = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .map(|(i, c)| c / (x + 1.0 + i as f64))
+        .sum();
+    -tmp + (2.5066282746310005 * (1.000000000190015 + series) / x).ln()
+}
+
+/// The regularized incomplete beta function `I_x(a, b)`, the CDF of a Beta(a, b) distribution.
+pub(crate) fn beta_cdf(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut result = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            result *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    result
+}
+
+/// The `p` quantile of a Beta(a, b) distribution, found by bisection.
+pub(crate) fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if beta_cdf(mid, a, b) < p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Draws from the standard normal distribution with the Box-Muller transform, given a source of
+/// uniform samples from `[0, 1)`.
+pub(crate) fn sample_standard_normal<R: FnMut() -> f64>(rng: &mut R) -> f64 {
+    let u1 = 1.0 - rng();
+    let u2 = rng();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Draws from a Gamma(shape, 1) distribution using the method of Marsaglia and Tsang.
+pub(crate) fn sample_gamma<R: FnMut() -> f64>(shape: f64, rng: &mut R) -> f64 {
+    if shape < 1.0 {
+        let u = 1.0 - rng();
+        return sample_gamma(shape + 1.0, rng) * u.powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = sample_standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v > 0.0 {
+            let u = 1.0 - rng();
+            if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+                return d * v;
+            }
+        }
+    }
+}
+
+pub(crate) fn sample_beta<R: FnMut() -> f64>(a: f64, b: f64, rng: &mut R) -> f64 {
+    let x = sample_gamma(a, rng);
+    let y = sample_gamma(b, rng);
+    x / (x + y)
+}
+
+/// A small xorshift generator standing in for a real random number generator in tests.
+#[cfg(test)]
+pub(crate) fn test_rng(seed: u64) -> impl FnMut() -> f64 {
+    let mut state = seed.max(1);
+    move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beta() {
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-10);
+        assert!((beta_cdf(0.3, 1.0, 1.0) - 0.3).abs() < 1e-12);
+        assert!((beta_cdf(0.5, 2.0, 2.0) - 0.5).abs() < 1e-12);
+        assert!((beta_cdf(0.2, 2.0, 3.0) - 0.1808).abs() < 1e-12);
+        assert!((beta_quantile(0.1808, 2.0, 3.0) - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sampling_moments() {
+        let mut rng = test_rng(42);
+        let n = 20000;
+        let gamma_mean = (0..n).map(|_| sample_gamma(3.0, &mut rng)).sum::<f64>() / n as f64;
+        assert!((gamma_mean - 3.0).abs() < 0.1);
+        let small_mean = (0..n).map(|_| sample_gamma(0.5, &mut rng)).sum::<f64>() / n as f64;
+        assert!((small_mean - 0.5).abs() < 0.05);
+        let beta_mean = (0..n).map(|_| sample_beta(2.0, 6.0, &mut rng)).sum::<f64>() / n as f64;
+        assert!((beta_mean - 0.25).abs() < 0.01);
+    }
+}
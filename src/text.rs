@@ -0,0 +1,58 @@
+//! # Text frequencies
+//! Shortcuts for counting the characters or words of a string. Words are separated by whitespace.
+//! `from_words_with()` applies a normalization function to each word first, such as lowercasing
+//! or stripping punctuation; words that normalize to the empty string are skipped.
+//!
+//! ```
+//! use hash_histogram::HashHistogram;
+//!
+//! let letters: HashHistogram<char> = HashHistogram::from_chars("hello");
+//! assert_eq!(letters.count(&'l'), 2);
+//!
+//! let text = "The cat saw the dog. The dog ran!";
+//! let words: HashHistogram<String> = HashHistogram::from_words(text);
+//! assert_eq!(words.count(&"The".to_string()), 2);
+//!
+//! let normalized: HashHistogram<String> = HashHistogram::from_words_with(text, |word| {
+//!     word.chars()
+//!         .filter(|c| c.is_alphanumeric())
+//!         .flat_map(char::to_lowercase)
+//!         .collect()
+//! });
+//! assert_eq!(normalized.count(&"the".to_string()), 3);
+//! assert_eq!(normalized.count(&"dog".to_string()), 2);
+//! ```
+
+use crate::{CounterType, HashHistogram};
+
+impl<C: CounterType> HashHistogram<char, C> {
+    pub fn from_chars(text: &str) -> Self {
+        text.chars().collect()
+    }
+}
+
+impl<C: CounterType> HashHistogram<String, C> {
+    pub fn from_words(text: &str) -> Self {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    pub fn from_words_with<F: FnMut(&str) -> String>(text: &str, normalize: F) -> Self {
+        text.split_whitespace()
+            .map(normalize)
+            .filter(|word| !word.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skips_empty_words() {
+        let h: HashHistogram<String, u32> =
+            HashHistogram::from_words_with("a -- b ... a", |w| w.replace(['-', '.'], ""));
+        assert_eq!(h.len(), 2);
+        assert_eq!(h.total_count(), 3);
+    }
+}
@@ -0,0 +1,102 @@
+//! # Document frequencies and TF-IDF
+//! `document_frequencies()` counts how many histograms in a collection contain each key with a
+//! positive count, regardless of how large that count is, as in finding which errors occur
+//! across the most hosts.
+//!
+//! ```
+//! use hash_histogram::{document_frequencies, HashHistogram};
+//!
+//! let hosts: Vec<HashHistogram<&str>> = vec![
+//!     ["timeout", "timeout", "timeout"].iter().collect(),
+//!     ["timeout", "refused"].iter().collect(),
+//!     ["disk full"].iter().collect(),
+//! ];
+//! let spread = document_frequencies(&hosts);
+//! assert_eq!(spread.count(&"timeout"), 2);
+//! assert_eq!(spread.count(&"refused"), 1);
+//! ```
+//!
+//! `tf_idf()` weights the terms of each document in a collection by how characteristic they are
+//! of that document. A term's weight is its term frequency, its count divided by the document's
+//! total count, times its inverse document frequency, `ln(n / df)`, where `n` is the number of
+//! documents and `df` is the number of documents containing the term. Terms found in every
+//! document therefore have weight zero.
+//!
+//! ```
+//! use hash_histogram::{tf_idf, HashHistogram};
+//!
+//! let documents: Vec<HashHistogram<String>> = ["the cat sat", "the dog sat", "the dog ran"]
+//!     .iter()
+//!     .map(|text| HashHistogram::from_words(text))
+//!     .collect();
+//! let weights = tf_idf(&documents);
+//! let cat = weights[0].weight(&"cat".to_string());
+//! assert!((cat - (3.0f64).ln() / 3.0).abs() < 1e-12);
+//! assert!(weights[0].weight(&"sat".to_string()) < cat);
+//! assert_eq!(weights[0].weight(&"the".to_string()), 0.0);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType, WeightedHistogram};
+
+pub fn document_frequencies<'a, T, C, I>(histograms: I) -> HashHistogram<T>
+where
+    T: 'a + KeyType,
+    C: 'a + CounterType,
+    I: IntoIterator<Item = &'a HashHistogram<T, C>>,
+{
+    let mut frequencies = HashHistogram::new();
+    for histogram in histograms {
+        frequencies.bump_all(
+            histogram
+                .iter()
+                .filter(|(_, c)| **c > num::zero())
+                .map(|(t, _)| t),
+        );
+    }
+    frequencies
+}
+
+/// Returns one weighted histogram per document, in the same order, with a weight for every
+/// term in the document.
+pub fn tf_idf<T: KeyType, C: CounterType>(
+    documents: &[HashHistogram<T, C>],
+) -> Vec<WeightedHistogram<T>> {
+    let frequencies = document_frequencies(documents);
+    let n = documents.len() as f64;
+    documents
+        .iter()
+        .map(|document| {
+            let total = document.total_count().to_f64().unwrap();
+            let mut weights = WeightedHistogram::new();
+            for (term, count) in document.iter().filter(|(_, c)| **c > num::zero()) {
+                let tf = count.to_f64().unwrap() / total;
+                let idf = (n / frequencies.count(term) as f64).ln();
+                weights.bump_by(term, tf * idf);
+            }
+            weights
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights() {
+        let documents: Vec<HashHistogram<char, u32>> = [
+            HashHistogram::from_chars("aab"),
+            HashHistogram::from_chars("c"),
+            HashHistogram::with_labels(['b']),
+        ]
+        .into();
+        let weights = tf_idf(&documents);
+        assert_eq!(weights.len(), 3);
+        assert!((weights[0].weight(&'a') - 2.0 / 3.0 * 3.0f64.ln()).abs() < 1e-12);
+        assert!((weights[0].weight(&'b') - 1.0 / 3.0 * 3.0f64.ln()).abs() < 1e-12);
+        assert!((weights[1].weight(&'c') - 3.0f64.ln()).abs() < 1e-12);
+        assert!(weights[2].is_empty());
+        assert_eq!(document_frequencies(&documents).count(&'b'), 1);
+        assert!(tf_idf::<char, usize>(&[]).is_empty());
+    }
+}
@@ -0,0 +1,393 @@
+//! # Weighted histograms
+//! `WeightedHistogram` accumulates floating-point weights per key, as in survey data where each
+//! respondent stands for a different share of the population. Alongside the total weight of each
+//! key, it tracks the sum of squared weights, from which it derives Kish's effective sample size
+//! and standard errors of the estimated proportions.
+//!
+//! ```
+//! use hash_histogram::WeightedHistogram;
+//!
+//! let mut h = WeightedHistogram::new();
+//! for (answer, weight) in [("yes", 1.0), ("no", 3.0), ("yes", 1.0), ("yes", 1.0)].iter() {
+//!     h.bump_by(answer, *weight);
+//! }
+//! assert_eq!(h.weight(&"yes"), 3.0);
+//! assert_eq!(h.total_weight(), 6.0);
+//! assert_eq!(h.proportion(&"no"), Some(0.5));
+//!
+//! // Unequal weights make the sample less informative than its four observations suggest.
+//! assert_eq!(h.effective_sample_size(), 3.0);
+//! assert_eq!(h.standard_error_of_proportion(&"no"), Some((0.25f64 / 3.0).sqrt()));
+//! ```
+//!
+//! Weights can be adjusted so that the histogram matches known population totals, either for
+//! the keys themselves (post-stratification) or, for pair keys, for both marginal distributions
+//! at once (raking, also known as iterative proportional fitting):
+//! ```
+//! use hash_histogram::WeightedHistogram;
+//! use std::collections::HashMap;
+//!
+//! let mut h = WeightedHistogram::new();
+//! for cell in [("urban", "young"), ("urban", "old"), ("rural", "old"), ("rural", "old")].iter() {
+//!     h.bump(cell);
+//! }
+//! let areas = HashMap::from([("urban", 60.0), ("rural", 40.0)]);
+//! let ages = HashMap::from([("young", 30.0), ("old", 70.0)]);
+//! assert!(h.rake_to(&areas, &ages, 1e-9, 100));
+//! assert!((h.weight(&("urban", "young")) - 30.0).abs() < 1e-6);
+//! assert!((h.total_weight() - 100.0).abs() < 1e-6);
+//! ```
+//!
+//! Floating-point sums accumulate rounding error, so weights within a relative tolerance
+//! (`epsilon()`) of one another are treated as tied when finding modes and ranking, with ties
+//! broken in favor of the smaller key:
+//! ```
+//! use hash_histogram::WeightedHistogram;
+//!
+//! let mut h = WeightedHistogram::new();
+//! h.bump_by(&"a", 0.1);
+//! h.bump_by(&"a", 0.2);
+//! h.bump_by(&"b", 0.3);
+//! h.bump_by(&"c", 0.25);
+//! assert_eq!(h.ranking(), vec!["a", "b", "c"]);
+//! assert_eq!(h.mode(), Some("a"));
+//! let mut modes = h.modes();
+//! modes.sort();
+//! assert_eq!(modes, vec!["a", "b"]);
+//! ```
+//!
+//! `merge_weighted()` blends the counts of several histograms, weighting each source:
+//! ```
+//! use hash_histogram::{merge_weighted, HashHistogram};
+//!
+//! let today: HashHistogram<&str> = ["song a", "song a", "song b"].iter().collect();
+//! let last_week: HashHistogram<&str> = ["song b", "song b", "song b"].iter().collect();
+//! let popularity = merge_weighted(&[(0.7, &today), (0.3, &last_week)]);
+//! assert!((popularity.weight(&"song a") - 1.4).abs() < 1e-12);
+//! assert!((popularity.weight(&"song b") - 1.6).abs() < 1e-12);
+//! ```
+
+use crate::{CounterType, HashHistogram, KeyType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+const DEFAULT_EPSILON: f64 = 1e-9;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WeightedHistogram<T: KeyType> {
+    histogram: HashMap<T, (f64, f64)>,
+    epsilon: f64,
+}
+
+impl<T: KeyType> Default for WeightedHistogram<T> {
+    fn default() -> Self {
+        Self::with_epsilon(DEFAULT_EPSILON)
+    }
+}
+
+impl<T: KeyType> WeightedHistogram<T> {
+    pub fn new() -> Self {
+        WeightedHistogram::default()
+    }
+
+    /// Creates a histogram treating weights as equal when they differ by at most `epsilon`
+    /// times the larger of 1 and their magnitudes. The default is `1e-9`.
+    pub fn with_epsilon(epsilon: f64) -> Self {
+        WeightedHistogram {
+            histogram: HashMap::new(),
+            epsilon,
+        }
+    }
+
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Returns `true` if `w1` and `w2` are equal within `epsilon()`.
+    pub fn weights_equal(&self, w1: f64, w2: f64) -> bool {
+        (w1 - w2).abs() <= self.epsilon * w1.abs().max(w2.abs()).max(1.0)
+    }
+
+    pub fn bump(&mut self, item: &T) {
+        self.bump_by(item, 1.0);
+    }
+
+    pub fn bump_by(&mut self, item: &T, weight: f64) {
+        match self.histogram.get_mut(item) {
+            None => {
+                self.histogram
+                    .insert(item.clone(), (weight, weight * weight));
+            }
+            Some((sum, sum_of_squares)) => {
+                *sum += weight;
+                *sum_of_squares += weight * weight;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.histogram.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    pub fn weight(&self, item: &T) -> f64 {
+        self.histogram.get(item).map_or(0.0, |(sum, _)| *sum)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, f64)> + '_ {
+        self.histogram.iter().map(|(key, (sum, _))| (key, *sum))
+    }
+
+    pub fn total_weight(&self) -> f64 {
+        self.iter().map(|(_, weight)| weight).sum()
+    }
+
+    pub fn ranking(&self) -> Vec<T>
+    where
+        T: Ord,
+    {
+        self.ranking_with_weights()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Keys paired with their weights, from heaviest to lightest. Starting from the heaviest,
+    /// each key within `epsilon()` of the heaviest key not yet ranked is tied with it, and tied
+    /// keys are listed in key order.
+    pub fn ranking_with_weights(&self) -> Vec<(T, f64)>
+    where
+        T: Ord,
+    {
+        let mut ranking: Vec<(T, f64)> = self.iter().map(|(k, w)| (k.clone(), w)).collect();
+        ranking.sort_by(|(_, w1), (_, w2)| w2.total_cmp(w1));
+        let mut start = 0;
+        while start < ranking.len() {
+            let heaviest = ranking[start].1;
+            let end = start
+                + ranking[start..]
+                    .iter()
+                    .take_while(|(_, w)| self.weights_equal(*w, heaviest))
+                    .count();
+            ranking[start..end].sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+            start = end;
+        }
+        ranking
+    }
+
+    /// The smallest of `modes()`.
+    pub fn mode(&self) -> Option<T>
+    where
+        T: Ord,
+    {
+        self.modes().into_iter().min()
+    }
+
+    /// All keys whose weights equal the largest weight within `epsilon()`.
+    pub fn modes(&self) -> Vec<T> {
+        match self.iter().map(|(_, w)| w).max_by(f64::total_cmp) {
+            None => vec![],
+            Some(max) => self
+                .iter()
+                .filter(|(_, w)| self.weights_equal(*w, max))
+                .map(|(key, _)| key.clone())
+                .collect(),
+        }
+    }
+
+    /// Share of the total weight belonging to `item`, or `None` if the total weight is zero.
+    pub fn proportion(&self, item: &T) -> Option<f64> {
+        let total = self.total_weight();
+        (total != 0.0).then(|| self.weight(item) / total)
+    }
+
+    /// Kish's effective sample size: the squared total weight divided by the sum of squared
+    /// weights. It equals the number of observations when all weights are equal.
+    pub fn effective_sample_size(&self) -> f64 {
+        let sum_of_squares: f64 = self.histogram.values().map(|(_, sq)| sq).sum();
+        if sum_of_squares == 0.0 {
+            0.0
+        } else {
+            self.total_weight().powi(2) / sum_of_squares
+        }
+    }
+
+    /// Standard error of `proportion(item)`, `sqrt(p * (1 - p) / n)` with `n` the effective
+    /// sample size. Returns `None` if the total weight is zero.
+    pub fn standard_error_of_proportion(&self, item: &T) -> Option<f64> {
+        let p = self.proportion(item)?;
+        Some((p * (1.0 - p) / self.effective_sample_size()).sqrt())
+    }
+
+    /// Post-stratifies the histogram: scales the observations of every key found in `targets`
+    /// so that its weight matches the target. Keys absent from `targets`, or with zero weight,
+    /// are left unchanged.
+    pub fn reweight_to(&mut self, targets: &HashMap<T, f64>) {
+        for (key, (sum, sum_of_squares)) in self.histogram.iter_mut() {
+            if let Some(target) = targets.get(key) {
+                if *sum != 0.0 {
+                    let factor = target / *sum;
+                    *sum *= factor;
+                    *sum_of_squares *= factor * factor;
+                }
+            }
+        }
+    }
+
+    fn scale_by_group<G: Hash + Eq>(
+        &mut self,
+        group: impl Fn(&T) -> &G,
+        targets: &HashMap<G, f64>,
+    ) -> f64 {
+        let mut current: HashMap<&G, f64> = HashMap::new();
+        for (key, (sum, _)) in self.histogram.iter() {
+            *current.entry(group(key)).or_default() += sum;
+        }
+        let mut max_error: f64 = 0.0;
+        let mut factors = HashMap::new();
+        for (g, total) in current {
+            if let Some(target) = targets.get(g) {
+                max_error = max_error.max((target - total).abs());
+                if total != 0.0 {
+                    factors.insert(g, target / total);
+                }
+            }
+        }
+        let factors: Vec<f64> = self
+            .histogram
+            .keys()
+            .map(|key| *factors.get(group(key)).unwrap_or(&1.0))
+            .collect();
+        for ((sum, sum_of_squares), factor) in self.histogram.values_mut().zip(factors) {
+            *sum *= factor;
+            *sum_of_squares *= factor * factor;
+        }
+        max_error
+    }
+}
+
+impl<A: KeyType, B: KeyType> WeightedHistogram<(A, B)> {
+    /// Rakes the histogram by alternately scaling the weights of pair keys so that the weights
+    /// grouped by first component match `first` and those grouped by second component match
+    /// `second`. Stops once neither marginal deviates from its targets by more than `tolerance`,
+    /// returning `true`, or after `max_iterations` rounds, returning `false`. Marginal categories
+    /// absent from the targets are left unscaled by that dimension.
+    pub fn rake_to(
+        &mut self,
+        first: &HashMap<A, f64>,
+        second: &HashMap<B, f64>,
+        tolerance: f64,
+        max_iterations: usize,
+    ) -> bool {
+        for _ in 0..max_iterations {
+            let first_error = self.scale_by_group(|(a, _)| a, first);
+            let second_error = self.scale_by_group(|(_, b)| b, second);
+            if first_error.max(second_error) <= tolerance {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Sums `weight * count` for each key over all sources. Each count is treated as that many
+/// observations carrying the weight of its source.
+pub fn merge_weighted<T: KeyType, C: CounterType>(
+    sources: &[(f64, &HashHistogram<T, C>)],
+) -> WeightedHistogram<T> {
+    let mut result = WeightedHistogram::new();
+    for (weight, histogram) in sources {
+        for (key, count) in histogram.iter() {
+            let count = count.to_f64().unwrap();
+            let (sum, sum_of_squares) = result.histogram.entry(key.clone()).or_insert((0.0, 0.0));
+            *sum += weight * count;
+            *sum_of_squares += weight * weight * count;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal_weights() {
+        let mut h = WeightedHistogram::new();
+        for i in 0..100 {
+            h.bump_by(&(i % 4 == 0), 2.5);
+        }
+        assert_eq!(h.len(), 2);
+        assert_eq!(h.total_weight(), 250.0);
+        assert!((h.effective_sample_size() - 100.0).abs() < 1e-9);
+        let se = h.standard_error_of_proportion(&true).unwrap();
+        assert!((se - (0.25f64 * 0.75 / 100.0).sqrt()).abs() < 1e-12);
+        assert_eq!(WeightedHistogram::<bool>::new().proportion(&true), None);
+        assert_eq!(
+            WeightedHistogram::<bool>::new().effective_sample_size(),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_epsilon_ties() {
+        let mut h = WeightedHistogram::with_epsilon(0.01);
+        h.bump_by(&1, 100.0);
+        h.bump_by(&2, 100.5);
+        h.bump_by(&3, 102.0);
+        assert_eq!(h.mode(), Some(3));
+        assert_eq!(h.modes(), vec![3]);
+        assert_eq!(h.ranking(), vec![3, 1, 2]);
+        assert!(h.weights_equal(100.0, 100.5));
+        assert!(!h.weights_equal(0.0, 0.5));
+        h.bump_by(&1, 1.9);
+        let mut modes = h.modes();
+        modes.sort();
+        assert_eq!(modes, vec![1, 3]);
+        assert_eq!(h.mode(), Some(1));
+        assert_eq!(h.ranking(), vec![1, 3, 2]);
+        assert_eq!(WeightedHistogram::<u8>::new().mode(), None);
+    }
+
+    #[test]
+    fn test_post_stratification() {
+        let mut h = WeightedHistogram::new();
+        h.bump_by(&"a", 1.0);
+        h.bump_by(&"a", 3.0);
+        h.bump_by(&"b", 2.0);
+        h.bump_by(&"c", 5.0);
+        let ess = h.effective_sample_size();
+        h.reweight_to(&HashMap::from([("a", 8.0), ("b", 1.0), ("d", 7.0)]));
+        assert_eq!(h.weight(&"a"), 8.0);
+        assert_eq!(h.weight(&"b"), 1.0);
+        assert_eq!(h.weight(&"c"), 5.0);
+        assert_eq!(h.weight(&"d"), 0.0);
+        assert!(h.effective_sample_size() != ess);
+    }
+
+    #[test]
+    fn test_raking_does_not_converge_with_inconsistent_totals() {
+        let mut h = WeightedHistogram::new();
+        h.bump(&(1, 'x'));
+        h.bump(&(2, 'y'));
+        let first = HashMap::from([(1, 10.0), (2, 10.0)]);
+        let second = HashMap::from([('x', 5.0), ('y', 5.0)]);
+        assert!(!h.rake_to(&first, &second, 1e-6, 20));
+    }
+
+    #[test]
+    fn test_merge_weighted_sample_size() {
+        let a: HashHistogram<char> = "xxxx".chars().collect();
+        let b: HashHistogram<char> = "yyyy".chars().collect();
+        let h = merge_weighted(&[(1.0, &a), (3.0, &b)]);
+        assert_eq!(h.total_weight(), 16.0);
+        assert_eq!(h.proportion(&'y'), Some(0.75));
+        assert!((h.effective_sample_size() - 6.4).abs() < 1e-12);
+    }
+}
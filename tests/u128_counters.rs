@@ -0,0 +1,73 @@
+use hash_histogram::{HashHistogram, RankTies};
+
+const HUGE: u128 = u64::MAX as u128 * 4;
+
+fn packets() -> HashHistogram<&'static str, u128> {
+    let mut h = HashHistogram::new();
+    h.bump_by(&"tcp", HUGE);
+    h.bump_by(&"udp", HUGE / 2);
+    h.bump(&"icmp");
+    h
+}
+
+#[test]
+fn test_totals_beyond_u64() {
+    let h = packets();
+    assert_eq!(h.total_count(), HUGE + HUGE / 2 + 1);
+    assert!(h.total_count() > u64::MAX as u128);
+    assert_eq!(h.max_count(), Some(HUGE));
+    assert_eq!(h.min_count(), Some(1));
+}
+
+#[test]
+fn test_ranking_and_mode() {
+    let h = packets();
+    assert_eq!(h.ranking(), vec!["tcp", "udp", "icmp"]);
+    assert_eq!(
+        h.ranking_with_counts(),
+        vec![("tcp", HUGE), ("udp", HUGE / 2), ("icmp", 1)]
+    );
+    assert_eq!(h.mode(), Some("tcp"));
+    assert_eq!(h.rank_of_with(&"icmp", RankTies::Dense), Some(2));
+}
+
+#[test]
+fn test_merging_and_scaling() {
+    let mut h = packets();
+    h.merge_with(&packets(), |a, b| a + b);
+    assert_eq!(h.count(&"tcp"), HUGE * 2);
+    assert_eq!(h.checked_bump_by(&"tcp", u128::MAX), None);
+    h.saturating_bump_by(&"udp", u128::MAX);
+    assert_eq!(h.count(&"udp"), u128::MAX);
+    assert_eq!((packets() * 3).count(&"icmp"), 3);
+}
+
+#[test]
+fn test_counts_of_counts_and_smoothing() {
+    let h = packets();
+    assert_eq!(h.counts_of_counts().count(&HUGE), 1);
+    let unseen = h.probability_of_unseen();
+    assert!(unseen > 0.0 && unseen < 1e-18);
+    assert!(h.good_turing_estimate(&"tcp") > h.good_turing_estimate(&"udp"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let h = packets();
+    let serialized = serde_json::to_string(&h).unwrap();
+    assert!(serialized.contains(&HUGE.to_string()));
+    let deserialized: HashHistogram<String, u128> = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized.count(&"tcp".to_string()), HUGE);
+    assert_eq!(deserialized.total_count(), h.total_count());
+}
+
+#[test]
+fn test_collecting() {
+    let h: HashHistogram<u8, u128> = [1, 2, 2].iter().collect();
+    assert_eq!(h.count(&2), 2);
+    let owned: HashHistogram<u8, u128> = [1u8, 2, 2].into_iter().collect();
+    assert_eq!(owned.count(&2), 2);
+    let pairs: HashHistogram<u8, u128> = [(1u8, HUGE), (1, 1)].into_iter().collect();
+    assert_eq!(pairs.count(&1), HUGE + 1);
+}